@@ -1,58 +1,42 @@
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::{
-    data::{Data, LN},
-    scrape::{self, Search},
+    cache::{self, Hit},
+    config::{Config, ReaderConfig, ReaderMode, TextAlign},
+    data::{is_corrupt_backup, Data, LN},
+    history::{self, HistoryEntry},
+    keys::{self, Action, OnAction},
+    migrate,
+    reader::{self, Find},
+    scrape::{self, Catalog, NovelDetails, Search, SearchResult},
     state::State,
-    Res,
+    stats::{self, Stats, Totals},
+    theme, Res,
 };
 
+use chrono::{Local, Utc};
 use cursive::theme::{Effect, Style};
 use cursive::utils::markup::StyledString;
 use cursive::views::NamedView;
 use cursive::{
     align::{Align, HAlign},
     direction::Orientation,
-    event::Key,
-    theme::{BaseColor, BorderStyle, Color, Palette, PaletteColor, Theme},
+    event::{Event, EventResult, Key},
+    theme::{BaseColor, Color},
     traits::{Nameable, Resizable, Scrollable},
-    view::Margins,
+    view::{Margins, Selector},
     views::{
-        DummyView, EditView, LinearLayout, OnEventView, PaddedView, Panel, SelectView, TextView,
-        ThemedView,
+        BoxedView, DummyView, EditView, LayerPosition, LinearLayout, OnEventView, PaddedView,
+        Panel, ScrollView, SelectView, TextView, ThemedView,
     },
-    Cursive, CursiveExt,
+    Cursive, CursiveExt, Rect, View,
 };
 use log::{error, info, LevelFilter};
 use owo_colors::OwoColorize;
 use parking_lot::RwLock;
 
-fn get_theme() -> Theme {
-    let path: PathBuf = "theme.toml".into();
-
-    if path.exists() {
-        cursive::theme::load_theme_file(path).expect("failed to load theme from `theme.toml`")
-    } else {
-        let mut t = Theme {
-            shadow: false,
-            borders: BorderStyle::Simple,
-            palette: Palette::default(),
-        };
-
-        t.palette[PaletteColor::Background] = Color::Dark(BaseColor::Black);
-        t.palette[PaletteColor::View] = Color::TerminalDefault;
-        t.palette[PaletteColor::Primary] = Color::Light(BaseColor::White);
-
-        t.palette[PaletteColor::Secondary] = Color::Light(BaseColor::Black);
-        t.palette[PaletteColor::Highlight] = Color::Dark(BaseColor::Black);
-        t.palette[PaletteColor::HighlightText] = Color::Light(BaseColor::White);
-        t.palette[PaletteColor::HighlightInactive] = Color::Light(BaseColor::Black);
-
-        t
-    }
-}
-
 pub fn run() -> Res<()> {
     let mut cursive = cursive::crossterm();
 
@@ -61,23 +45,505 @@ pub fn run() -> Res<()> {
     log::set_max_level(LevelFilter::Info);
 
     let siv = &mut cursive;
-    {
-        let theme = get_theme();
-        siv.set_theme(theme);
-    }
+
+    let (theme, theme_error) = theme::configured();
+    siv.set_theme(theme);
 
     siv.add_active_screen();
 
-    siv.add_global_callback('q', Cursive::quit);
-    siv.add_global_callback('D', Cursive::toggle_debug_console);
+    if let Some(event) = keys::event(Action::Quit) {
+        siv.add_global_callback(event, |s| {
+            record_reading(s);
+            s.quit();
+        });
+    }
+    if let Some(event) = keys::event(Action::DebugConsole) {
+        siv.add_global_callback(event, Cursive::toggle_debug_console);
+    }
+
+    for (from, to) in keys::translations() {
+        siv.set_on_pre_event(from.clone(), move |s| {
+            let event = if typing(s) { from.clone() } else { to.clone() };
 
-    home_view(siv, &None);
+            send(s, event);
+        });
+    }
+
+    if let Some(event) = keys::half_page_down() {
+        siv.set_on_pre_event(event.clone(), move |s| half_page(s, &event, Key::Down));
+    }
+    if let Some(event) = keys::half_page_up() {
+        siv.set_on_pre_event(event.clone(), move |s| half_page(s, &event, Key::Up));
+    }
+
+    home_view(siv, None);
+
+    // the defaults are used for anything that couldn't be read
+    if let Some(e) = theme_error {
+        error_panel(siv, &e.to_string());
+    }
 
     siv.run_crossterm()?;
 
     Ok(())
 }
 
+/// the names of the text inputs, which get keys as they are instead of translated by the keymap
+const TEXT_INPUTS: [&str; 5] = [
+    "chapter_input",
+    "tags_input",
+    "search_input",
+    "find_input",
+    "chapter_search_input",
+];
+
+/// whether the top layer has a text input, which is then what has focus
+fn typing(siv: &mut Cursive) -> bool {
+    let Some(layer) = siv.screen_mut().get_mut(LayerPosition::FromFront(0)) else {
+        return false;
+    };
+
+    let mut found = false;
+
+    for name in TEXT_INPUTS {
+        layer.call_on_any(&Selector::Name(name), &mut |_| found = true);
+    }
+
+    found
+}
+
+/// send an event straight to the views, skipping the global callbacks
+fn send(siv: &mut Cursive, event: Event) {
+    if let EventResult::Consumed(Some(cb)) = siv.screen_mut().on_event(event) {
+        cb(siv);
+    }
+}
+
+/// move half a screen up or down, one line at a time so lists and text both follow
+fn half_page(siv: &mut Cursive, event: &Event, key: Key) {
+    if typing(siv) {
+        send(siv, event.clone());
+        return;
+    }
+
+    for _ in 0..(siv.screen_size().y / 2).max(1) {
+        send(siv, Event::Key(key));
+    }
+}
+
+/// the key hints along the bottom of the reader
+fn reader_hints(mode: ReaderMode) -> StyledString {
+    let mut s = StyledString::new();
+
+    keys::hint(&mut s, Action::Quit, "quit, ");
+    keys::hint(&mut s, Action::ChapterSelect, "chapter select, ");
+    keys::hint(&mut s, Action::Home, "home view, ");
+    keys::hint(&mut s, Action::OpenInBrowser, "Open in browser, ");
+    keys::hint(&mut s, Action::ReadingMode, "mode, ");
+
+    if mode != ReaderMode::Continuous {
+        keys::hint(&mut s, Action::Find, "find, ");
+    }
+
+    if mode == ReaderMode::Auto {
+        keys::hint(&mut s, Action::PauseScroll, "pause, ");
+        s.append_styled(
+            format!(
+                "{}/{}",
+                keys::name(Action::ScrollSlower),
+                keys::name(Action::ScrollFaster)
+            ),
+            Color::Dark(BaseColor::Yellow),
+        );
+        s.append_plain(" speed, ");
+    }
+
+    s.append_styled(
+        format!(
+            "{}/{}",
+            keys::name(Action::WiderText),
+            keys::name(Action::NarrowerText)
+        ),
+        Color::Dark(BaseColor::Yellow),
+    );
+    s.append_plain(" width, ");
+    s.append_styled(
+        format!(
+            "{}/{}",
+            keys::name(Action::PreviousChapter),
+            keys::name(Action::NextChapter)
+        ),
+        Color::Dark(BaseColor::Yellow),
+    );
+    s.append_plain(" to navigate");
+
+    s
+}
+
+const fn text_align(config: &ReaderConfig) -> HAlign {
+    match config.align {
+        TextAlign::Center => HAlign::Center,
+        TextAlign::Left | TextAlign::Justified => HAlign::Left,
+    }
+}
+
+/// the chapters shown one after another in continuous mode
+struct Continuous {
+    /// each chapter with the line it starts on
+    chapters: Vec<(usize, State)>,
+    /// the line after the last chapter
+    end: usize,
+    config: ReaderConfig,
+    width: usize,
+    /// set once there's nothing more to add, so reaching the end again doesn't retry
+    done: bool,
+}
+
+/// follow the chapter at the top of the screen, and add the next one once the end is in sight
+fn keep_reading(siv: &mut Cursive, viewport: Rect, continuous: &RwLock<Continuous>) {
+    let (current, at_end) = {
+        let continuous = continuous.read();
+
+        (
+            continuous
+                .chapters
+                .iter()
+                .rev()
+                .find(|(start, _)| *start <= viewport.top())
+                .map(|(_, state)| state.clone()),
+            !continuous.done && viewport.bottom() + 1 >= continuous.end,
+        )
+    };
+
+    let reading = siv.user_data::<State>().map(|x| x.chapter);
+
+    if let Some(current) = current.filter(|x| Some(x.chapter) != reading) {
+        info!("continued to chapter {}", current.chapter);
+
+        record_reading(siv);
+        save_progress(siv, &current);
+
+        siv.call_on_name("title", |view: &mut TextView| {
+            view.set_content(reader_header(&current));
+        });
+        siv.set_user_data(State {
+            opened_at: Some(Utc::now()),
+            ..current
+        });
+    }
+
+    if at_end {
+        add_next_chapter(siv, continuous);
+    }
+}
+
+/// load the chapter after the last one shown and put it below, after a divider with its title
+fn add_next_chapter(siv: &mut Cursive, continuous: &RwLock<Continuous>) {
+    let last = continuous.read().chapters.last().map(|(_, x)| x.clone());
+
+    let Some(last) = last.filter(|x| x.chapter < x.max_chapters) else {
+        continuous.write().done = true;
+        return;
+    };
+
+    let url = last.url.replace(
+        &format!("chapter-{}", last.chapter),
+        &format!("chapter-{}", last.chapter + 1),
+    );
+
+    let state = match scrape::load(&url) {
+        Ok(output) => {
+            if let Err(e) = cache::store(&url, &output) {
+                error!("Failed to keep a copy of the chapter: {e}");
+            }
+
+            State::from_output(&url, output)
+        }
+        Err(e) => {
+            continuous.write().done = true;
+            error!("Failed to load the next chapter: {e}");
+            error_panel(siv, &format!("Failed to load the next chapter: {e}"));
+            return;
+        }
+    };
+
+    let mut divider = StyledString::plain("── ");
+    divider.append_styled(&state.title, Color::Dark(BaseColor::Green));
+    divider.append_plain(" ──");
+
+    let (divider, formatted, align) = {
+        let mut continuous = continuous.write();
+        let formatted = reader::format(&state.document, &continuous.config, continuous.width);
+
+        // the divider has a blank line above and below it
+        let start = continuous.end + reader::rows(&divider, continuous.width) + 2;
+
+        continuous.end = start + reader::rows(&formatted, continuous.width);
+        continuous.chapters.push((start, state));
+
+        (divider, formatted, text_align(&continuous.config))
+    };
+
+    siv.call_on_name("chapters", |view: &mut LinearLayout| {
+        view.add_child(PaddedView::lrtb(
+            0,
+            0,
+            1,
+            1,
+            TextView::new(divider).center(),
+        ));
+        view.add_child(TextView::new(formatted).h_align(align));
+    });
+}
+
+/// the chapter's title and number along the top of the reader
+fn reader_header(state: &State) -> StyledString {
+    let mut s = StyledString::new();
+
+    s.append_styled(&state.title, Color::Dark(BaseColor::Green));
+    s.append_plain(" - ");
+    s.append_styled(format!("{}", state.chapter), Color::Dark(BaseColor::Yellow));
+    s.append_plain("/");
+    s.append_styled(
+        format!("{}", state.max_chapters),
+        Color::Dark(BaseColor::Yellow),
+    );
+
+    s
+}
+
+/// run `f` with the chapter being read, which changes while scrolling in continuous mode
+fn on_current(f: impl Fn(&mut Cursive, &State) + Clone) -> impl Fn(&mut Cursive) + Clone {
+    move |siv| {
+        if let Some(state) = siv.user_data::<State>().cloned() {
+            f(siv, &state);
+        }
+    }
+}
+
+/// flip through `pages` with the page keys
+fn add_page_keys(layout: &mut OnEventView<LinearLayout>, pages: Vec<StyledString>) {
+    let pages = Arc::new(pages);
+    let next = {
+        let pages = Arc::clone(&pages);
+
+        on_current(move |siv, state| flip_page(siv, state, &pages, true))
+    };
+    let previous = on_current(move |siv, state| flip_page(siv, state, &pages, false));
+
+    layout.set_on_action(Action::NextPage, next.clone());
+    layout.set_on_event(Key::PageDown, next);
+    layout.set_on_action(Action::PreviousPage, previous.clone());
+    layout.set_on_event(Key::PageUp, previous);
+}
+
+/// cut the chapter into pages that fit between the header and the hints
+fn reader_pages(
+    siv: &Cursive,
+    config: &ReaderConfig,
+    text: reader::Layout,
+    formatted: &StyledString,
+) -> Vec<StyledString> {
+    let size = siv.screen_size();
+
+    // the header takes two lines, and the hints as many as they wrap onto
+    let footer = reader_hints(config.mode)
+        .width()
+        .div_ceil(size.x.max(1))
+        .max(1);
+
+    reader::pages(
+        formatted,
+        text.text_width,
+        size.y.saturating_sub(2 + footer),
+    )
+}
+
+/// search the chapter, and go between the matches
+fn add_find_keys(layout: &mut OnEventView<LinearLayout>) {
+    layout.set_on_action(Action::Find, find_view);
+    layout.set_on_action(Action::NextMatch, |siv| {
+        show_matches(siv, Some(true));
+    });
+    layout.set_on_action(Action::PreviousMatch, |siv| {
+        show_matches(siv, Some(false));
+    });
+}
+
+/// ask what to look for in the chapter, picking out the matches while it's typed
+fn find_view(siv: &mut Cursive) {
+    let query = siv
+        .user_data::<State>()
+        .and_then(|x| x.find.as_ref())
+        .map(|x| x.query.clone())
+        .unwrap_or_default();
+
+    let input = EditView::new()
+        .content(query)
+        .on_edit(|siv, text, _| {
+            siv.with_user_data(|state: &mut State| {
+                state.find = Some(Find {
+                    query: text.to_owned(),
+                    current: 0,
+                });
+            });
+
+            show_matches(siv, None);
+        })
+        .on_submit(|siv, _| {
+            siv.pop_layer();
+        });
+
+    let panel = OnEventView::new(
+        Panel::new(input.with_name(TEXT_INPUTS[3]).fixed_width(40)).title("Find in chapter"),
+    )
+    .on_event(Key::Esc, |siv| {
+        siv.pop_layer();
+        siv.with_user_data(|state: &mut State| state.find = None);
+        show_matches(siv, None);
+    });
+
+    siv.add_layer(panel);
+}
+
+/// pick out the matches of the search in the chapter, and bring the current one, or the next
+/// or previous one, into view
+fn show_matches(siv: &mut Cursive, forward: Option<bool>) {
+    let Some(mut state) = siv.user_data::<State>().cloned() else {
+        return;
+    };
+
+    let config = Config::load().unwrap_or_default().reader;
+    let text = reader::layout(siv.screen_size().x, &config);
+    let formatted = reader::format(&state.document, &config, text.text_width);
+    let query = state
+        .find
+        .as_ref()
+        .map(|x| x.query.clone())
+        .unwrap_or_default();
+
+    if config.mode == ReaderMode::Paged {
+        let pages = reader_pages(siv, &config, text, &formatted);
+        let found: Vec<usize> = pages
+            .iter()
+            .enumerate()
+            .flat_map(|(i, page)| {
+                reader::find_in(page.source(), &query)
+                    .into_iter()
+                    .map(move |_| i)
+            })
+            .collect();
+
+        if let Some(find) = &mut state.find {
+            find.step(found.len(), forward);
+
+            if let Some(page) = found.get(find.current) {
+                state.position = reader::position_of(&pages, *page);
+            }
+        }
+
+        let page = reader::page_at(&pages, state.position);
+
+        siv.call_on_name("reader_page", |view: &mut TextView| {
+            view.set_content(reader::highlight_page(&pages, page, state.find.as_ref()));
+        });
+        siv.call_on_name("title", |view: &mut TextView| {
+            view.set_content(paged_header(&state, &pages));
+        });
+    } else {
+        let found = reader::find_in(formatted.source(), &query);
+        let row = state.find.as_mut().and_then(|find| {
+            find.step(found.len(), forward);
+            found
+                .get(find.current)
+                .map(|x| reader::row_of(&formatted, text.text_width, x.start))
+        });
+
+        siv.call_on_name("reader_scroll", |view: &mut ScrollView<TextView>| {
+            view.get_inner_mut()
+                .set_content(reader::highlight(&formatted, state.find.as_ref(), 0));
+
+            // a little below the top, so what leads up to it shows too
+            if let Some(row) = row {
+                let height = view.content_viewport().height();
+                view.set_offset((0, row.saturating_sub(height / 3)));
+            }
+        });
+    }
+
+    siv.set_user_data(state);
+}
+
+/// the chapter's text, all of it or just the current page, along with the pages in paged mode
+fn reader_content(
+    siv: &Cursive,
+    state: &State,
+    config: &ReaderConfig,
+    text: reader::Layout,
+) -> (Box<dyn View>, Option<Vec<StyledString>>) {
+    let formatted = reader::format(&state.document, config, text.text_width);
+    let align = text_align(config);
+    let margins = Margins {
+        left: text.margin,
+        right: text.margin,
+        top: 0,
+        bottom: 0,
+    };
+
+    match config.mode {
+        ReaderMode::Scroll | ReaderMode::Auto => {
+            let main_content = TextView::new(reader::highlight(&formatted, state.find.as_ref(), 0))
+                .h_align(align)
+                .scrollable()
+                .with_name("reader_scroll");
+            let main_content = ThemedView::new(siv.current_theme().clone(), main_content);
+
+            return (
+                Box::new(PaddedView::new(margins, main_content).full_height()),
+                None,
+            );
+        }
+        ReaderMode::Continuous => {
+            let continuous = Arc::new(RwLock::new(Continuous {
+                end: reader::rows(&formatted, text.text_width),
+                chapters: vec![(0, state.clone())],
+                config: config.clone(),
+                width: text.text_width,
+                done: false,
+            }));
+
+            let mut main_content = LinearLayout::vertical()
+                .child(TextView::new(formatted).h_align(align))
+                .with_name("chapters")
+                .scrollable();
+            main_content.set_on_scroll_change(move |siv, viewport| {
+                keep_reading(siv, viewport, &continuous);
+            });
+            let main_content = ThemedView::new(siv.current_theme().clone(), main_content);
+
+            return (
+                Box::new(PaddedView::new(margins, main_content).full_height()),
+                None,
+            );
+        }
+        ReaderMode::Paged => {}
+    }
+
+    let pages = reader_pages(siv, config, text, &formatted);
+    let page = reader::page_at(&pages, state.position);
+
+    let main_content = TextView::new(reader::highlight_page(&pages, page, state.find.as_ref()))
+        .h_align(align)
+        .with_name("reader_page")
+        .full_height();
+    let main_content = ThemedView::new(siv.current_theme().clone(), main_content);
+
+    (
+        Box::new(PaddedView::new(margins, main_content)),
+        Some(pages),
+    )
+}
+
 fn reader_view(siv: &mut Cursive) {
     info!("reader view");
 
@@ -85,160 +551,407 @@ fn reader_view(siv: &mut Cursive) {
 
     let Some(state) = state else {
         siv.pop_layer();
-        home_view(siv, &None);
+        home_view(siv, None);
         error_panel(
             siv,
-            "Nothing is configured to be read. Please use `s`, or select from the home screen.",
+            &format!(
+                "Nothing is configured to be read. Please use `{}`, or select from the home screen.",
+                keys::name(Action::Search)
+            ),
         );
         return;
     };
 
     {
-        let state = state.clone();
+        let mut state = state.clone();
+        state.opened_at.get_or_insert_with(Utc::now);
         siv.set_user_data(state);
     }
 
-    let size = siv.screen_size();
+    let config = Config::load().unwrap_or_default().reader;
+    let text = reader::layout(siv.screen_size().x, &config);
+    let (main_content, pages) = reader_content(siv, &state, &config, text);
 
-    let main_content = TextView::new(&state.content)
-        .h_align(HAlign::Center)
-        .full_height();
-    let main_content = ThemedView::new(get_theme(), main_content);
-    let margins = Margins {
-        left: if size.x == 0 { 8 } else { size.x / 8 },
-        right: if size.x == 0 { 8 } else { size.x / 8 },
-        top: 0,
-        bottom: 0,
+    let auto = (config.mode == ReaderMode::Auto)
+        .then(|| Arc::new(RwLock::new(AutoScroll::new(config.auto_scroll))));
+
+    let header = match (&pages, &auto) {
+        (Some(pages), _) => paged_header(&state, pages),
+        (_, Some(auto)) => auto_header(&state, &auto.read()),
+        _ => reader_header(&state),
     };
 
+    // only auto mode needs to be woken up without any input
+    siv.set_fps(if auto.is_some() { AUTO_SCROLL_FPS } else { 0 });
+
     let layout = LinearLayout::vertical()
         .child(
-            TextView::new({
-                let mut s = StyledString::new();
-
-                s.append_styled(&state.title, Color::Dark(BaseColor::Green));
-                s.append_plain(" - ");
-                s.append_styled(format!("{}", state.chapter), Color::Dark(BaseColor::Yellow));
-                s.append_plain("/");
-                s.append_styled(
-                    format!("{}", state.max_chapters),
-                    Color::Dark(BaseColor::Yellow),
-                );
-                s
-            })
-            .center()
-            .fixed_height(2)
-            .with_name("title"),
+            TextView::new(header)
+                .center()
+                .with_name("title")
+                .fixed_height(2),
         )
-        .child(PaddedView::new(margins, main_content.scrollable()).with_name("main_content"))
+        .child(BoxedView::new(main_content).with_name("main_content"))
         .child(
-            TextView::new({
-                let mut s = StyledString::new();
-
-                s.append_styled("q", Color::Dark(BaseColor::Yellow));
-                s.append_plain("uit, ");
-                s.append_styled("c", Color::Dark(BaseColor::Yellow));
-                s.append_plain("hapter select, ");
-                s.append_styled("h", Color::Dark(BaseColor::Yellow));
-                s.append_plain("ome view, ");
-                s.append_styled("s", Color::Dark(BaseColor::Yellow));
-                s.append_plain("earch, ");
-                s.append_styled("arrow keys", Color::Dark(BaseColor::Yellow));
-                s.append_plain(" to navigate");
-
-                s
-            })
-            .align(Align::bot_right())
-            .with_name("footer"),
+            TextView::new(reader_hints(config.mode))
+                .align(Align::bot_right())
+                .with_name("footer"),
         );
 
-    let s1 = state.clone();
-    let s2 = state.clone();
-    let s3 = state.clone();
+    let text_width = text.text_width;
 
-    let layout = OnEventView::new(layout)
-        .on_event('c', move |siv| {
-            select_chapter(siv, &s1.clone());
+    let mut layout = OnEventView::new(layout)
+        .on_action(Action::ChapterSelect, on_current(select_chapter))
+        .on_action(Action::NextChapter, on_current(next_chapter))
+        .on_action(Action::PreviousChapter, on_current(previous_chapter))
+        .on_action(Action::Home, |siv| {
+            record_reading(siv);
+            siv.set_fps(0);
+            home_view(siv, None);
         })
-        .on_event(Key::Right, move |siv| {
-            next_chapter(siv, &s2.clone());
+        .on_action(Action::ReadingMode, move |siv| {
+            switch_mode(siv, config.mode.next());
         })
-        .on_event(Key::Left, move |siv| {
-            previous_chapter(siv, &s3.clone());
+        .on_action(Action::WiderText, move |siv| {
+            resize_text(siv, text_width + WIDTH_STEP);
         })
-        .on_event('h', |siv| {
-            home_view(siv, &None);
+        .on_action(Action::NarrowerText, move |siv| {
+            resize_text(siv, text_width.saturating_sub(WIDTH_STEP));
         })
-        .on_event('O', move |siv| {
-            let res = open::that(&state.url);
+        .on_action(
+            Action::OpenInBrowser,
+            on_current(|siv, state| {
+                let res = open::that(&state.url);
 
-            let Err(e) = res else {
-                return;
-            };
+                let Err(e) = res else {
+                    return;
+                };
 
-            {
-                error_panel(siv, "Could not open the url.");
-                error!("Could not open the url: {e}");
-            }
+                {
+                    error_panel(siv, "Could not open the url.");
+                    error!("Could not open the url: {e}");
+                }
+            }),
+        );
+
+    if let Some(pages) = pages {
+        add_page_keys(&mut layout, pages);
+    }
+
+    if config.mode == ReaderMode::Continuous {
+        layout.set_on_action(Action::Find, |siv| {
+            info_panel(
+                siv,
+                "Finding in the chapter is not available in continuous mode.",
+            );
         });
+    } else {
+        add_find_keys(&mut layout);
+    }
+
+    if let Some(auto) = auto {
+        add_auto_keys(&mut layout, &auto);
+    }
+
+    // pages depend on the size of the screen
+    if config.mode == ReaderMode::Paged {
+        layout.set_on_event(Event::WindowResize, reader_view);
+    }
 
     siv.pop_layer();
     siv.add_fullscreen_layer(layout);
 
-    siv.clear_global_callbacks('r');
+    if let Some(event) = keys::event(Action::Reader) {
+        siv.clear_global_callbacks(event);
+    }
 }
 
-fn select_chapter(siv: &mut Cursive, state: &State) {
-    info!("select chapter");
+/// how often auto mode checks whether it's time to scroll
+const AUTO_SCROLL_FPS: u32 = 10;
+
+/// how much the speed keys change the auto mode speed by, in lines per minute
+const SPEED_STEP: usize = 10;
+
+/// the pace of auto mode, and how far it has got
+struct AutoScroll {
+    /// lines per minute
+    speed: usize,
+    paused: bool,
+    /// when the last line was scrolled, or the scrolling was resumed
+    since: Instant,
+    /// lines that were due after the end was reached, to leave time to read the last screen
+    overrun: usize,
+}
 
-    let range = 1..=state.max_chapters;
+impl AutoScroll {
+    fn new(speed: usize) -> Self {
+        Self {
+            speed: speed.max(SPEED_STEP),
+            paused: false,
+            since: Instant::now(),
+            overrun: 0,
+        }
+    }
 
-    let state = state.clone();
-    let chapter_select = EditView::new()
-        .content(state.chapter.to_string())
-        .max_content_width(5)
-        .on_submit(move |s, text| {
-            let text = text.trim();
+    /// how many lines should be scrolled by now
+    fn due(&mut self) -> usize {
+        if self.paused {
+            return 0;
+        }
 
-            if text.is_empty() {
-                s.pop_layer();
-                error_panel(s, "please enter a chapter number"); // TODO: Nicer error messages
-                return;
-            }
+        let per_line =
+            Duration::from_secs(60) / u32::try_from(self.speed.max(1)).unwrap_or(u32::MAX);
+        let mut lines = 0;
 
-            let chapter = text.parse::<usize>();
+        while self.since.elapsed() >= per_line {
+            self.since += per_line;
+            lines += 1;
+        }
 
-            let chapter = match chapter {
-                Ok(chapter) => chapter,
-                Err(e) => {
-                    s.pop_layer();
-                    error_panel(
-                        s,
-                        &format!("please enter a valid number ({e})",), // TODO: Nicer error messages
-                    );
-                    return;
-                }
-            };
+        lines
+    }
+}
 
-            if !range.contains(&chapter) {
-                s.pop_layer();
-                error_panel(s, "please enter a number inside the range"); // TODO: Nicer error messages
-                return;
-            }
+/// the header of the reader in paged mode, with the page after the chapter
+fn paged_header(state: &State, pages: &[StyledString]) -> StyledString {
+    let mut s = reader_header(state);
 
-            let pat = format!("chapter-{}", state.chapter);
-            let url = state
-                .url
-                .replace(pat.as_str(), &format!("chapter-{chapter}"));
+    s.append_plain(" - page ");
+    s.append_styled(
+        format!("{}", reader::page_at(pages, state.position) + 1),
+        Color::Dark(BaseColor::Yellow),
+    );
+    s.append_plain("/");
+    s.append_styled(format!("{}", pages.len()), Color::Dark(BaseColor::Yellow));
 
-            load_url(s, &url);
+    s
+}
 
-            s.pop_layer();
+/// the header of the reader in auto mode, with the speed after the chapter
+fn auto_header(state: &State, auto: &AutoScroll) -> StyledString {
+    let mut s = reader_header(state);
+
+    s.append_plain(" - ");
+    s.append_styled(format!("{}", auto.speed), Color::Dark(BaseColor::Yellow));
+    s.append_plain(" lines/min");
+
+    if auto.paused {
+        s.append_styled(" (paused)", Color::Dark(BaseColor::Red));
+    }
+
+    s
+}
+
+/// scroll on a line at a time in auto mode, and pause or change speed with the keys
+fn add_auto_keys(layout: &mut OnEventView<LinearLayout>, auto: &Arc<RwLock<AutoScroll>>) {
+    layout.set_on_pre_event(Event::Refresh, {
+        let auto = Arc::clone(auto);
+        move |siv| auto_scroll(siv, &auto)
+    });
+    layout.set_on_action(Action::PauseScroll, {
+        let auto = Arc::clone(auto);
+        move |siv| {
+            {
+                let mut auto = auto.write();
+                auto.paused = !auto.paused;
+                auto.since = Instant::now();
+            }
+
+            show_speed(siv, &auto.read());
+        }
+    });
+    layout.set_on_action(Action::ScrollFaster, {
+        let auto = Arc::clone(auto);
+        move |siv| change_speed(siv, &auto, true)
+    });
+    layout.set_on_action(Action::ScrollSlower, {
+        let auto = Arc::clone(auto);
+        move |siv| change_speed(siv, &auto, false)
+    });
+}
+
+/// scroll down the lines that are due, and go on to the next chapter a screen after the end
+fn auto_scroll(siv: &mut Cursive, auto: &RwLock<AutoScroll>) {
+    let lines = auto.write().due();
+
+    if lines == 0 {
+        return;
+    }
+
+    let scrolled = siv.call_on_name("reader_scroll", |view: &mut ScrollView<TextView>| {
+        let at_bottom = view.is_at_bottom();
+        let viewport = view.content_viewport();
+
+        view.set_offset(viewport.top_left() + (0, lines));
+
+        (at_bottom, viewport.height())
+    });
+
+    let Some((true, height)) = scrolled else {
+        return;
+    };
+
+    let done = {
+        let mut auto = auto.write();
+        auto.overrun += lines;
+        auto.overrun >= height
+    };
+
+    let Some(state) = siv.user_data::<State>().cloned().filter(|_| done) else {
+        return;
+    };
+
+    if state.chapter == state.max_chapters {
+        auto.write().paused = true;
+        show_speed(siv, &auto.read());
+        return;
+    }
+
+    next_chapter(siv, &state);
+}
+
+/// make auto mode faster or slower, and remember the new speed
+fn change_speed(siv: &mut Cursive, auto: &RwLock<AutoScroll>, faster: bool) {
+    let speed = {
+        let mut auto = auto.write();
+
+        auto.speed = if faster {
+            auto.speed + SPEED_STEP
+        } else {
+            auto.speed.saturating_sub(SPEED_STEP).max(SPEED_STEP)
+        };
+
+        auto.speed
+    };
+
+    show_speed(siv, &auto.read());
+
+    if let Err(e) = Config::set(
+        Some("reader"),
+        "auto_scroll",
+        i64::try_from(speed).unwrap_or(i64::MAX),
+    ) {
+        error_panel(siv, &format!("Failed to save the scrolling speed: {e}"));
+        error!("Failed to save the scrolling speed: {e}");
+    }
+}
+
+fn show_speed(siv: &mut Cursive, auto: &AutoScroll) {
+    let Some(state) = siv.user_data::<State>().cloned() else {
+        return;
+    };
+
+    siv.call_on_name("title", |view: &mut TextView| {
+        view.set_content(auto_header(&state, auto));
+    });
+}
+
+/// go to the next or previous page, or on to the next chapter after the last one
+fn flip_page(siv: &mut Cursive, state: &State, pages: &[StyledString], forward: bool) {
+    let page = reader::page_at(pages, state.position);
+
+    let page = if forward {
+        if page + 1 == pages.len() {
+            next_chapter(siv, state);
+            return;
+        }
+
+        page + 1
+    } else if page == 0 {
+        return;
+    } else {
+        page - 1
+    };
+
+    siv.with_user_data(|state: &mut State| {
+        state.position = reader::position_of(pages, page);
+    });
+
+    reader_view(siv);
+}
+
+/// switch to another way of reading, and remember it
+fn switch_mode(siv: &mut Cursive, mode: ReaderMode) {
+    if let Err(e) = Config::set(Some("reader"), "mode", mode.name()) {
+        error_panel(siv, &format!("Failed to save the reading mode: {e}"));
+        error!("Failed to save the reading mode: {e}");
+        return;
+    }
+
+    reader_view(siv);
+}
+
+/// how much the width keys change the text width by
+const WIDTH_STEP: usize = 4;
+
+/// save a new text width and show the chapter with it
+fn resize_text(siv: &mut Cursive, width: usize) {
+    let width = width.max(reader::MIN_WIDTH);
+
+    if let Err(e) = Config::set(
+        Some("reader"),
+        "width",
+        i64::try_from(width).unwrap_or(i64::MAX),
+    ) {
+        error_panel(siv, &format!("Failed to save the text width: {e}"));
+        error!("Failed to save the text width: {e}");
+        return;
+    }
+
+    reader_view(siv);
+}
+
+fn select_chapter(siv: &mut Cursive, state: &State) {
+    info!("select chapter");
+
+    let range = 1..=state.max_chapters;
+
+    let state = state.clone();
+    let chapter_select = EditView::new()
+        .content(state.chapter.to_string())
+        .max_content_width(5)
+        .on_submit(move |s, text| {
+            let text = text.trim();
+
+            if text.is_empty() {
+                s.pop_layer();
+                error_panel(s, "please enter a chapter number"); // TODO: Nicer error messages
+                return;
+            }
+
+            let chapter = text.parse::<usize>();
+
+            let chapter = match chapter {
+                Ok(chapter) => chapter,
+                Err(e) => {
+                    s.pop_layer();
+                    error_panel(
+                        s,
+                        &format!("please enter a valid number ({e})"), // TODO: Nicer error messages
+                    );
+                    return;
+                }
+            };
+
+            if !range.contains(&chapter) {
+                s.pop_layer();
+                error_panel(s, "please enter a number inside the range"); // TODO: Nicer error messages
+                return;
+            }
+
+            let pat = format!("chapter-{}", state.chapter);
+            let url = state
+                .url
+                .replace(pat.as_str(), &format!("chapter-{chapter}"));
+
+            load_url(s, &url);
+
+            s.pop_layer();
             reader_view(s);
         });
 
     let layout = LinearLayout::vertical()
-        .child(chapter_select)
+        .child(chapter_select.with_name(TEXT_INPUTS[0]))
         .child(TextView::new({
             let mut s = StyledString::new();
 
@@ -277,609 +990,1818 @@ fn error_panel(siv: &mut Cursive, err: &str) {
             TextView::new({
                 let mut s = StyledString::new();
 
-                s.append_plain("Press ");
-                s.append_styled("esc", Color::Dark(BaseColor::Yellow));
-                s.append_plain(" to close");
+                s.append_plain("Press ");
+                s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+                s.append_plain(" to close");
+
+                s
+            })
+            .center(),
+        );
+
+    let panel = Panel::new(layout).title("Error");
+    let panel = OnEventView::new(panel).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    siv.add_layer(panel);
+}
+
+fn previous_chapter(siv: &mut Cursive, state: &State) {
+    siv.pop_layer();
+
+    if state.chapter == 1 {
+        load_url(siv, &state.url);
+        reader_view(siv);
+        error_panel(siv, "You are already on the first chapter");
+        return;
+    }
+
+    let url = state.url.replace(
+        &format!("chapter-{}", state.chapter),
+        &format!("chapter-{}", state.chapter - 1),
+    );
+
+    load_url(siv, &url);
+
+    siv.pop_layer();
+    reader_view(siv);
+}
+
+fn next_chapter(siv: &mut Cursive, state: &State) {
+    info!("next chapter");
+
+    if state.chapter == state.max_chapters {
+        error_panel(siv, "You are already on the last chapter");
+        return;
+    }
+
+    let url = state.url.replace(
+        &format!("chapter-{}", state.chapter),
+        &format!("chapter-{}", state.chapter + 1),
+    );
+
+    load_url(siv, &url);
+
+    siv.pop_layer();
+    reader_view(siv);
+}
+
+fn home_view(siv: &mut Cursive, updates: Option<&Vec<LN>>) {
+    info!("home view");
+
+    siv.pop_layer();
+
+    siv.add_fullscreen_layer(DummyView);
+
+    let mut load_error = None;
+    let existed = Data::exists().unwrap_or(true);
+
+    let data = match Data::load() {
+        Ok(data) => data,
+        // only start afresh when there's really nothing stored, not when it couldn't be checked
+        Err(err) if matches!(Data::exists(), Ok(false)) => {
+            let res = Data::new().save();
+
+            assert!(res.is_ok(), "Failed to generate data. Please check that {:?} is accessible by your user. Error was: {}",
+                Data::data_folder().yellow(), err.yellow());
+
+            // the file was there before we tried to load it, so it was moved aside as corrupted
+            if existed {
+                load_error = Some(err);
+            }
+
+            Data::new()
+        }
+        Err(err) => {
+            error!("Failed to load data: {err}");
+            load_error = Some(err);
+
+            Data::new()
+        }
+    };
+
+    let mut main_view = LinearLayout::vertical();
+
+    main_view.add_child(
+        TextView::new({
+            let mut s = StyledString::new();
+
+            s.append_styled("Welcome to `lncli`", Effect::Bold);
+
+            s
+        })
+        .center()
+        .full_width(),
+    );
+
+    let mut tracked_count = StyledString::new();
+
+    tracked_count.append_plain("There are currently ");
+    tracked_count.append_styled(
+        format!("{}", data.tracked().len()),
+        Color::Dark(BaseColor::Yellow),
+    );
+    tracked_count.append_plain(" tracked novels.");
+
+    main_view.add_child(TextView::new(tracked_count).center().full_width());
+    main_view.add_child(
+        TextView::new(
+            "The `update check` will also add an asterisk if you aren't caught up, so be aware.",
+        )
+        .center()
+        .full_width(),
+    );
+
+    let submit = |s: &mut Cursive, novel: &LN| {
+        let ch = format!("/chapter-{}.html", novel.last_chapter);
+        load_url(s, &novel.url.replace(".html", &ch));
+        reader_view(s);
+    };
+
+    let tv = create_tv(&data, updates, submit);
+
+    let tracked_panel = Panel::new(tv).title("Tracked Novels").full_screen();
+
+    let rv = create_rv(&data, submit);
+
+    let recent_panel = Panel::new(rv).title("Recent Novels").full_screen();
+
+    main_view.add_child(
+        LinearLayout::horizontal()
+            .child(tracked_panel)
+            .child(recent_panel),
+    );
+
+    let controls_text = get_home_controls();
+
+    main_view.add_child(TextView::new(controls_text).align(Align::bot_right()));
+
+    let main_view = OnEventView::new(main_view)
+        .on_action(Action::Reader, reader_view)
+        .on_action(Action::Search, |s| {
+            search_view(s, None);
+        })
+        .on_action(Action::UpdateCheck, |s| {
+            update_check(s);
+        })
+        .on_action(Action::Browse, browse_view)
+        .on_action(Action::Backups, backup_view)
+        .on_action(Action::History, history_view)
+        .on_action(Action::Stats, stats_view)
+        .on_action(Action::SearchChapters, chapter_search_view)
+        .on_action(Action::Themes, theme_view);
+
+    siv.add_fullscreen_layer(main_view.full_height());
+
+    if let Some(err) = load_error {
+        error_panel(
+            siv,
+            &format!(
+                "Could not load your library. Use `{}` to restore a backup.\n{err}",
+                keys::name(Action::Backups)
+            ),
+        );
+    }
+}
+
+fn get_home_controls() -> StyledString {
+    let mut text = StyledString::new();
+
+    keys::hint(&mut text, Action::Quit, "quit, ");
+    keys::hint(&mut text, Action::Reader, "reader, ");
+    keys::hint(&mut text, Action::Track, "track/untrack, ");
+    keys::hint(&mut text, Action::Tags, "Tags, ");
+    keys::hint(&mut text, Action::Migrate, "Migrate, ");
+    keys::hint(&mut text, Action::RemoveRecent, "delete recent, ");
+    keys::hint(&mut text, Action::Search, "search, ");
+    keys::hint(&mut text, Action::Browse, "browse, ");
+    keys::hint(&mut text, Action::UpdateCheck, "update check, ");
+    keys::hint(&mut text, Action::Backups, "Backups, ");
+    keys::hint(&mut text, Action::History, "History, ");
+    keys::hint(&mut text, Action::Stats, "Stats, ");
+    keys::hint(
+        &mut text,
+        Action::SearchChapters,
+        "Find in saved chapters, ",
+    );
+    keys::hint(&mut text, Action::Themes, "Colors, ");
+    keys::hint(&mut text, Action::Details, "info, ");
+    text.append_styled("enter", Color::Dark(BaseColor::Yellow));
+    text.append_plain(" to select, ");
+    text.append_styled("arrow keys", Color::Dark(BaseColor::Yellow));
+    text.append_plain(" to navigate");
+
+    text
+}
+
+fn create_rv(data: &Data, submit: fn(&mut Cursive, &LN)) -> OnEventView<NamedView<SelectView<LN>>> {
+    let label = |name: &str, last_chapter: usize| {
+        let mut s = StyledString::new();
+
+        s.append_styled(
+            name,
+            Style::merge(&[Color::Dark(BaseColor::Red).into(), Effect::Bold.into()]),
+        );
+        s.append_plain(" (");
+        s.append_styled(
+            format!("{last_chapter}"),
+            Style::merge(&[Color::Dark(BaseColor::Magenta).into(), Effect::Bold.into()]),
+        );
+        s.append_plain(")");
+
+        s
+    };
+
+    let rv = {
+        let mut sv = SelectView::new();
+        for x in data.recent() {
+            sv.add_item(label(&x.name, x.last_chapter), x.clone());
+        }
+
+        sv.set_on_submit(submit);
+
+        sv.h_align(HAlign::Center).with_name("recent_view")
+    };
+
+    let data = data.clone();
+    let removal_data = data.clone();
+
+    OnEventView::new(rv)
+        .on_action(Action::Track, move |s| {
+            let mut data = data.clone();
+            let rv = s.find_name::<SelectView<LN>>("recent_view");
+
+            if rv.is_none() {
+                return;
+            }
+            let rv = rv.expect("Failed to find recent view");
+
+            let selected = rv.selected_id();
+
+            if selected.is_none() {
+                return;
+            }
+
+            let selected = selected.expect("Failed to get selected item");
+            let item = rv.get_item(selected).expect("Failed to get selected item");
+
+            data.add_tracked(item.1.clone());
+
+            if let Err(e) = data.save() {
+                error_panel(s, &format!("Failed to save data: {e}"));
+                error!("Failed to save data: {e}");
+            }
+
+            home_view(s, None);
+        })
+        .on_action(Action::RemoveRecent, move |s| {
+            let Some(rv) = s.find_name::<SelectView<LN>>("recent_view") else {
+                return;
+            };
+
+            let Some(novel) = rv.selection() else {
+                return;
+            };
+
+            let mut data = removal_data.clone();
+            data.remove_recent(&novel);
+
+            if let Err(e) = data.save() {
+                error_panel(s, &format!("Failed to save data: {e}"));
+                error!("Failed to save data: {e}");
+                return;
+            }
+
+            home_view(s, None);
+        })
+}
+
+fn create_tv(
+    data: &Data,
+    updates: Option<&Vec<LN>>,
+    submit: fn(&mut Cursive, &LN),
+) -> OnEventView<NamedView<SelectView<LN>>> {
+    let label = |novel: &LN, update: bool| {
+        let mut s = StyledString::new();
+
+        if update {
+            s.append_styled(
+                "*",
+                Style::merge(&[Color::Dark(BaseColor::Magenta).into(), Effect::Bold.into()]),
+            );
+            s.append_plain(" ");
+        }
+
+        s.append_styled(
+            &novel.name,
+            Style::merge(&[Color::Dark(BaseColor::Red).into(), Effect::Bold.into()]),
+        );
+        s.append_plain(" (");
+        s.append_styled(
+            format!("{}", novel.last_chapter),
+            Style::merge(&[Color::Dark(BaseColor::Magenta).into(), Effect::Bold.into()]),
+        );
+        s.append_plain(")");
+
+        if !novel.tags.is_empty() {
+            s.append_plain(" [");
+            s.append_styled(novel.tags.join(", "), Color::Dark(BaseColor::Cyan));
+            s.append_plain("]");
+        }
+
+        s
+    };
+
+    let updates = updates.cloned();
+    let tv = {
+        let mut sv = SelectView::new();
+        for x in data.tracked() {
+            if let Some(updates) = &updates {
+                if updates.iter().any(|u| u.id == x.id) {
+                    sv.add_item(label(x, true), x.clone());
+                } else {
+                    sv.add_item(label(x, false), x.clone());
+                }
+            } else {
+                sv.add_item(label(x, false), x.clone());
+            }
+        }
+
+        sv.set_on_submit(submit);
+
+        sv.h_align(HAlign::Center).with_name("tracked_view")
+    };
+
+    let data = data.clone();
+
+    OnEventView::new(tv)
+        .on_action(Action::Track, move |s| {
+            let updates = &updates;
+            let mut data = data.clone();
+            let sv = s.find_name::<SelectView<LN>>("tracked_view");
+
+            if sv.is_none() {
+                return;
+            }
+            let sv = sv.expect("Failed to get select view");
+
+            let selected = sv.selected_id();
+
+            if selected.is_none() {
+                return;
+            }
+
+            let selected = selected.expect("Failed to get selected item");
+            let item = sv
+                .get_item(selected)
+                .expect("Failed to get selected item from SelectView");
+
+            data.tracked_mut().retain(|x| x != item.1);
+
+            if let Err(e) = data.save() {
+                error_panel(s, &format!("Failed to save data: {e}"));
+                error!("Failed to save data: {e}");
+            }
+
+            home_view(s, updates.as_ref());
+        })
+        .on_action(Action::Details, |s| {
+            let sv = s.find_name::<SelectView<LN>>("tracked_view");
+
+            let Some(sv) = sv else {
+                return;
+            };
+
+            let Some(item) = sv.selection() else {
+                return;
+            };
+
+            details_view(s, &item.url);
+        })
+        .on_action(Action::Tags, |s| {
+            let sv = s.find_name::<SelectView<LN>>("tracked_view");
+
+            let Some(sv) = sv else {
+                return;
+            };
+
+            let Some(item) = sv.selection() else {
+                return;
+            };
+
+            edit_tags(s, &item);
+        })
+        .on_action(Action::Migrate, |s| {
+            let sv = s.find_name::<SelectView<LN>>("tracked_view");
+
+            let Some(sv) = sv else {
+                return;
+            };
+
+            let Some(item) = sv.selection() else {
+                return;
+            };
+
+            migrate_view(s, &item);
+        })
+}
+
+fn edit_tags(siv: &mut Cursive, novel: &LN) {
+    info!("edit tags");
+
+    let id = novel.id.clone();
+    let tags = EditView::new()
+        .content(novel.tags.join(", "))
+        .on_submit(move |s, text| {
+            let data = Data::load();
+
+            let Ok(mut data) = data else {
+                s.pop_layer();
+                error_panel(s, "Failed to load data");
+                return;
+            };
+
+            let tags = text
+                .split(',')
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>();
+
+            data.tracked_mut()
+                .iter_mut()
+                .filter(|x| x.id == id)
+                .for_each(|x| x.tags.clone_from(&tags));
+
+            s.pop_layer();
+
+            if let Err(e) = data.save() {
+                error_panel(s, &format!("Failed to save data: {e}"));
+                error!("Failed to save data: {e}");
+                return;
+            }
+
+            home_view(s, None);
+        });
+
+    let layout = LinearLayout::vertical()
+        .child(tags.with_name(TEXT_INPUTS[1]).min_width(30))
+        .child(TextView::new({
+            let mut s = StyledString::new();
+
+            s.append_plain("Separate tags with ");
+            s.append_styled(",", Color::Dark(BaseColor::Yellow));
+            s.append_plain(".\nAnd press ");
+            s.append_styled("Enter", Color::Dark(BaseColor::Yellow));
+
+            s
+        }));
+
+    let panel = OnEventView::new(Panel::new(layout).title("Tags")).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    siv.add_layer(panel);
+}
+
+fn update_check(cursive: &mut Cursive) {
+    info!("update check");
+
+    let data = Data::load();
+
+    if data.is_err() {
+        error_panel(cursive, "Failed to load data");
+        return;
+    }
+
+    let mut data = data.expect("Failed to load data");
+
+    let mut found = Vec::new();
+
+    for x in data.tracked() {
+        let check = scrape::update_check(&x.url, x.last_chapter);
+
+        check.map_or_else(
+            |ch| {
+                error_panel(cursive, &format!("Failed to check for updates: {ch}"));
+            },
+            |check| {
+                if check {
+                    found.push(x.clone());
+                }
+            },
+        );
+    }
+
+    if data.update_urls() > 0 {
+        if let Err(e) = data.save() {
+            error!("Failed to save the updated urls: {e}");
+        }
+    }
+
+    home_view(cursive, Some(&found));
+}
+
+fn load_url(siv: &mut Cursive, url: &str) {
+    info!("LOAD_URL: Attempting to load: {}", url.green());
+    let output = scrape::load(url);
+
+    if let Err(e) = output {
+        error_panel(siv, &e.to_string());
+        panic!("{}", e);
+    }
+
+    let output = output.expect("Failed to get output");
+
+    if let Err(e) = cache::store(url, &output) {
+        error!("Failed to keep a copy of the chapter: {e}");
+    }
+
+    let state = State::from_output(url, output);
+
+    info!(
+        "LOAD_URL: Successfully loaded state from url {}",
+        &state.url,
+    );
+
+    open_chapter(siv, state);
+}
+
+/// make `state` the chapter being read, after recording the one before it
+fn open_chapter(siv: &mut Cursive, state: State) {
+    record_reading(siv);
+    save_progress(siv, &state);
+
+    siv.set_user_data(state);
+}
+
+/// put the chapter in the recent list, and make it the last one read if the novel is tracked
+fn save_progress(siv: &mut Cursive, state: &State) {
+    let url = &state.url;
+    let data = Data::load();
+
+    let mut data = match data {
+        Ok(data) => data,
+        Err(_) if matches!(Data::exists(), Ok(false)) => Data::new(),
+        Err(e) => {
+            error!("Failed to load data, not recording progress: {e}");
+            error_panel(siv, &format!("Failed to load data: {e}"));
+            return;
+        }
+    };
+
+    let mut recent = LN::new(
+        state.name.clone(),
+        {
+            // ugly ass hack
+            let a = url.split_once("/chapter").expect("Failed to split url");
+            let mut a = a.0.to_string();
+            a.push_str(".html");
+
+            a
+        },
+        state.chapter,
+    );
+
+    if let Some(novel) = data.tracked_mut().iter_mut().find(|x| x.is_at(url)) {
+        info!(
+            "found tracked novel {}, updating latest chapter read",
+            novel.id
+        );
+
+        novel.last_chapter = state.chapter;
+        // so the recent entry is the same novel, even when its url has moved on from its id
+        recent.id.clone_from(&novel.id);
+    }
+
+    data.push_recent(recent);
+
+    data.update_urls();
+
+    let save_res = data.save();
+
+    if let Err(e) = save_res {
+        panic!("{}", e);
+    }
+}
+
+fn search_view(siv: &mut Cursive, results: Option<Search>) {
+    let results_mode = results.is_some();
+    let browse_mode = results.as_ref().is_some_and(|x| x.catalog.is_some());
+    let search_box = {
+        let ev = EditView::new().on_submit(move |s, text| {
+            let text = text.trim();
+
+            if text.is_empty() {
+                error_panel(s, "please enter a search term");
+                return;
+            }
+
+            if text.len() < 3 {
+                error_panel(s, "please enter at least 3 characters");
+                return;
+            }
+
+            search_url(s, text);
+        });
+
+        if let Some(results) = &results {
+            ev.content(&results.query)
+        } else {
+            ev
+        }
+    };
+
+    let search_results = { results.as_ref().map(create_sv) };
+    let has_more = results.as_ref().is_some_and(|x| x.next_page.is_some());
+
+    let search_layout = {
+        let mut layout = LinearLayout::vertical();
+
+        if let Some(search_results) = search_results {
+            let results = results.expect("Failed to get query from search results");
+            let query = format!(
+                "{} ({} results, page {})",
+                results.query.trim(),
+                results.results.len(),
+                results.page
+            );
+            let query = query.as_str();
+
+            layout.add_child(
+                TextView::new({
+                    let mut s = StyledString::new();
+
+                    s.append_styled(query, Color::Dark(BaseColor::Green));
+
+                    s
+                })
+                .h_align(HAlign::Center)
+                .no_wrap(),
+            );
+            layout.add_child(
+                TextView::new("-".repeat(query.len() + 2))
+                    .h_align(HAlign::Center)
+                    .no_wrap(),
+            );
+
+            layout.add_child(
+                TextView::new(search_header())
+                    .h_align(HAlign::Left)
+                    .no_wrap(),
+            );
+            layout.add_child(search_results.scrollable());
+        } else {
+            layout.add_child(search_box.with_name(TEXT_INPUTS[2]));
+        }
+
+        layout
+    };
+
+    let results_mode_text = search_controls(results_mode, has_more);
+
+    let controls = TextView::new(results_mode_text).align(Align::bot_right());
+
+    let layout = LinearLayout::new(Orientation::Vertical)
+        .child(search_layout)
+        .child(controls);
+
+    let panel = Panel::new(layout).title(if browse_mode {
+        "Browse"
+    } else if results_mode {
+        "Search Results"
+    } else {
+        "Search"
+    });
+
+    let view = OnEventView::new(panel).on_event(Key::Esc, |s| {
+        s.pop_layer();
+        s.pop_layer();
+
+        home_view(s, None);
+    });
+
+    siv.add_layer(view);
+}
+
+fn search_controls(results_mode: bool, has_more: bool) -> StyledString {
+    let mut s = StyledString::new();
+
+    if results_mode {
+        keys::hint(&mut s, Action::Track, "track, ");
+        keys::hint(&mut s, Action::Details, "info, ");
+        if has_more {
+            keys::hint(&mut s, Action::MoreResults, "more results, ");
+        }
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to select, ");
+        s.append_styled("arrow keys", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to navigate");
+    } else {
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to search");
+    }
+
+    s
+}
+
+/// pad or truncate `text` so it takes up exactly `width` columns
+fn fit(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+
+    if count > width {
+        let mut text = text.chars().take(width - 1).collect::<String>();
+        text.push('…');
+        text
+    } else {
+        format!("{text}{}", " ".repeat(width - count))
+    }
+}
+
+const SEARCH_COLUMNS: [usize; 4] = [40, 20, 8, 10];
+
+fn search_header() -> StyledString {
+    let [title, author, chapters, status] = SEARCH_COLUMNS;
+    let mut s = StyledString::new();
+
+    s.append_styled(
+        format!(
+            "{} {} {} {} Genres",
+            fit("Title", title),
+            fit("Author", author),
+            fit("Chapters", chapters),
+            fit("Status", status)
+        ),
+        Effect::Bold,
+    );
+
+    s
+}
+
+fn search_row(result: &SearchResult) -> StyledString {
+    let [title, author, chapters, status] = SEARCH_COLUMNS;
+    let mut s = StyledString::new();
+
+    s.append_styled(fit(&result.title, title), Color::Dark(BaseColor::Green));
+    s.append_plain(" ");
+    s.append_plain(fit(result.author.as_deref().unwrap_or("-"), author));
+    s.append_plain(" ");
+    s.append_styled(
+        fit(
+            &result
+                .latest_chapter
+                .map_or_else(|| "-".to_owned(), |x| x.to_string()),
+            chapters,
+        ),
+        Color::Dark(BaseColor::Yellow),
+    );
+    s.append_plain(" ");
+    s.append_plain(fit(&result.status.to_string(), status));
+    s.append_plain(" ");
+    s.append_styled(result.genres.join(", "), Color::Dark(BaseColor::Magenta));
+
+    s
+}
+
+fn create_sv(search: &Search) -> cursive::views::OnEventView<cursive::views::SelectView<url::Url>> {
+    let mut sv = SelectView::new().h_align(HAlign::Left);
+
+    if search.results.is_empty() {
+        sv.set_on_submit(|s, _| {
+            s.pop_layer();
+            error_panel(s, "No results found.\nTry a shorter query.");
+        });
+        return OnEventView::new(sv);
+    }
+
+    for result in &search.results {
+        sv.add_item(search_row(result), result.url.clone());
+    }
+
+    sv.set_on_submit(|s, url| {
+        let url = url.as_str();
+
+        let url = url.replace(".html", "/chapter-1.html");
+
+        load_url(s, &url);
+        s.pop_layer();
+        reader_view(s);
+    });
+
+    let selected = Arc::new(RwLock::new(Some(search.results[0].url.to_string())));
+
+    let si = selected.clone();
+    sv.set_on_select(move |_, url| {
+        let url = url.as_str();
+
+        si.write_arc().replace(url.to_owned());
+    });
+
+    let s1 = selected.clone();
+    let search = search.clone();
+    let search2 = search.clone();
+
+    OnEventView::new(sv)
+        .on_action(Action::MoreResults, move |s| {
+            if search.next_page.is_none() {
+                return;
+            }
+
+            let output = scrape::search_more(&search);
+
+            let output = match output {
+                Ok(output) => output,
+                Err(e) => {
+                    error_panel(s, &e.to_string());
+                    return;
+                }
+            };
+
+            s.pop_layer();
+            search_view(s, Some(output));
+        })
+        .on_action(Action::Details, move |s| {
+            let sel = s1.read_arc().clone();
+
+            let Some(sel) = sel else {
+                info!("Selected was none");
+                return;
+            };
+
+            details_view(s, &sel);
+        })
+        .on_action(Action::Track, move |s| {
+            let sel = {
+                let selected = selected.clone().read_arc();
+                if selected.is_none() {
+                    info!("Selected was none");
+                    return;
+                }
+                selected.clone().expect("No selected value found")
+            };
+
+            info!("Selected: {}", sel.green());
+
+            let data = Data::load();
+
+            if let Ok(mut data) = data {
+                let result = search2.results.iter().find(|x| x.url.as_str() == sel);
+
+                data.add_tracked(LN {
+                    status: result.map(|x| x.status).unwrap_or_default(),
+                    ..LN::new(
+                        result.map_or_else(
+                            || scrape::get_name(&sel).expect("Failed to get name from selection"),
+                            |x| x.title.clone(),
+                        ),
+                        sel.clone(),
+                        1,
+                    )
+                });
+
+                let data = data.save();
+
+                if let Err(e) = data {
+                    error_panel(s, &e.to_string());
+                }
+            }
+        })
+}
+
+fn search_url(siv: &mut Cursive, query: &str) {
+    info!("search query: {}", query.green());
+
+    let output = scrape::search(query);
+
+    siv.pop_layer();
+    if let Err(e) = &output {
+        search_view(siv, None);
+        error_panel(siv, &e.to_string());
+        return;
+    }
+
+    let output = output.expect("Failed to get search results");
+
+    search_view(siv, Some(output));
+}
+
+fn browse_view(siv: &mut Cursive) {
+    info!("browse view");
+
+    let mut sv = SelectView::new().h_align(HAlign::Center);
+
+    sv.add_item("Latest Release", Some(Catalog::LatestRelease));
+    sv.add_item("Most Popular", Some(Catalog::MostPopular));
+    sv.add_item("Completed", Some(Catalog::Completed));
+    sv.add_item("Genres...", None);
+
+    sv.set_on_submit(|s, catalog: &Option<Catalog>| {
+        if let Some(catalog) = catalog {
+            browse_catalog(s, catalog);
+        } else {
+            genre_view(s);
+        }
+    });
+
+    let controls = {
+        let mut s = StyledString::new();
+
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to select");
+
+        s
+    };
+
+    let layout = LinearLayout::vertical()
+        .child(sv)
+        .child(TextView::new(controls).align(Align::bot_right()));
+
+    let view = OnEventView::new(Panel::new(layout).title("Browse")).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    siv.add_layer(view);
+}
+
+fn genre_view(siv: &mut Cursive) {
+    info!("genre view");
+
+    let mut sv = SelectView::new().h_align(HAlign::Center);
+
+    for genre in scrape::GENRES {
+        sv.add_item(*genre, Catalog::Genre((*genre).to_owned()));
+    }
+
+    sv.set_on_submit(|s, catalog| {
+        s.pop_layer();
+        browse_catalog(s, catalog);
+    });
+
+    let view =
+        OnEventView::new(Panel::new(sv.scrollable()).title("Genres")).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+
+    siv.add_layer(view);
+}
+
+fn browse_catalog(siv: &mut Cursive, catalog: &Catalog) {
+    info!("browse catalog: {}", catalog.green());
+
+    let output = scrape::browse(catalog);
+
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            error_panel(siv, &e.to_string());
+            return;
+        }
+    };
+
+    siv.pop_layer();
+    search_view(siv, Some(output));
+}
+
+fn backup_view(siv: &mut Cursive) {
+    info!("backup view");
+
+    let backups = match Data::backups() {
+        Ok(backups) => backups,
+        Err(e) => {
+            error_panel(siv, &format!("Failed to list backups: {e}"));
+            return;
+        }
+    };
+
+    if backups.is_empty() {
+        error_panel(siv, "There are no backups yet.");
+        return;
+    }
+
+    let mut sv = SelectView::new().h_align(HAlign::Center);
+
+    for backup in backups {
+        let name = backup
+            .file_name()
+            .map_or_else(String::new, |x| x.to_string_lossy().to_string());
+
+        let mut label = StyledString::new();
+
+        label.append_styled(&name, Color::Dark(BaseColor::Green));
+
+        match Data::read_backup(&backup) {
+            Ok(data) => {
+                label.append_plain(" (");
+                label.append_styled(
+                    format!("{}", data.tracked().len()),
+                    Color::Dark(BaseColor::Yellow),
+                );
+                label.append_plain(" tracked)");
+            }
+            Err(_) if is_corrupt_backup(&backup) => {
+                label.append_styled(" (corrupted)", Color::Dark(BaseColor::Red));
+            }
+            Err(_) => {
+                label.append_styled(" (unreadable)", Color::Dark(BaseColor::Red));
+            }
+        }
+
+        sv.add_item(label, backup);
+    }
+
+    sv.set_on_submit(|s, backup: &PathBuf| {
+        if let Err(e) = Data::restore(backup) {
+            error_panel(s, &format!("Failed to restore backup: {e}"));
+            error!("Failed to restore backup: {e}");
+            return;
+        }
 
-                s
-            })
-            .center(),
-        );
+        info!("restored backup {}", backup.display());
 
-    let panel = Panel::new(layout).title("Error");
-    let panel = OnEventView::new(panel).on_event(Key::Esc, |s| {
         s.pop_layer();
+        home_view(s, None);
     });
 
-    siv.add_layer(panel);
+    let controls = {
+        let mut s = StyledString::new();
+
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to restore");
+
+        s
+    };
+
+    let layout = LinearLayout::vertical()
+        .child(sv.scrollable())
+        .child(TextView::new(controls).align(Align::bot_right()));
+
+    let view =
+        OnEventView::new(Panel::new(layout).title("Restore Backup")).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+
+    siv.add_layer(view);
 }
 
-fn previous_chapter(siv: &mut Cursive, state: &State) {
-    siv.pop_layer();
+/// add the chapter currently in the reader to the history, along with how long it was open
+fn record_reading(siv: &mut Cursive) {
+    let Some(state) = siv.user_data::<State>() else {
+        return;
+    };
 
-    if state.chapter == 1 {
-        load_url(siv, &state.url);
-        reader_view(siv);
-        error_panel(siv, "You are already on the first chapter");
+    let Some(opened_at) = state.opened_at.take() else {
         return;
+    };
+
+    let entry = HistoryEntry {
+        name: state.name.clone(),
+        url: state.novel_url(),
+        chapter: state.chapter,
+        chapter_title: state.title.clone(),
+        opened_at,
+        seconds: u64::try_from((Utc::now() - opened_at).num_seconds()).unwrap_or_default(),
+        words: state.content.split_whitespace().count(),
+        max_chapters: state.max_chapters,
+    };
+
+    if let Err(e) = Data::append_history(&entry) {
+        error!("Failed to record reading history: {e}");
     }
+}
 
-    let url = state.url.replace(
-        &format!("chapter-{}", state.chapter),
-        &format!("chapter-{}", state.chapter - 1),
-    );
+/// how far back the history view goes, exports have all of it
+const HISTORY_DAYS: i64 = 90;
 
-    load_url(siv, &url);
+fn history_view(siv: &mut Cursive) {
+    info!("history view");
 
-    siv.pop_layer();
-    reader_view(siv);
-}
+    let since = Utc::now() - chrono::Duration::days(HISTORY_DAYS);
 
-fn next_chapter(siv: &mut Cursive, state: &State) {
-    info!("next chapter");
+    let history = match Data::history_since(since) {
+        Ok(history) => history,
+        Err(e) => {
+            error_panel(siv, &format!("Failed to load the reading history: {e}"));
+            return;
+        }
+    };
 
-    if state.chapter == state.max_chapters {
-        error_panel(siv, "You are already on the last chapter");
+    if history.is_empty() {
+        error_panel(
+            siv,
+            &format!("There is no reading history from the last {HISTORY_DAYS} days."),
+        );
         return;
     }
 
-    let url = state.url.replace(
-        &format!("chapter-{}", state.chapter),
-        &format!("chapter-{}", state.chapter + 1),
-    );
+    let mut sv = SelectView::new();
 
-    load_url(siv, &url);
+    for entry in history.iter().rev() {
+        let mut label = StyledString::new();
 
-    siv.pop_layer();
-    reader_view(siv);
-}
+        label.append_plain(
+            entry
+                .opened_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M  ")
+                .to_string(),
+        );
+        label.append_styled(&entry.name, Color::Dark(BaseColor::Green));
+        label.append_plain(" - ");
+        label.append_styled(format!("{}", entry.chapter), Color::Dark(BaseColor::Yellow));
+        label.append_plain(format!(" ({})", history::format_duration(entry.seconds)));
 
-fn home_view(siv: &mut Cursive, updates: &Option<Vec<LN>>) {
-    info!("home view");
+        sv.add_item(label, entry.clone());
+    }
 
-    siv.pop_layer();
+    sv.set_on_submit(|s, entry: &HistoryEntry| {
+        s.pop_layer();
+        load_url(s, &entry.chapter_url());
+        reader_view(s);
+    });
 
-    siv.add_fullscreen_layer(DummyView);
+    let controls = {
+        let mut s = StyledString::new();
 
-    let mut data = Data::load();
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        keys::hint(&mut s, Action::ClearHistory, "clear, ");
+        keys::hint(&mut s, Action::ExportHistory, "export, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to reopen");
 
-    if data.is_err() {
-        let err = data.expect_err("guaranteed to be an error");
-        error_panel(siv, "Data missing/corrupted. Regenerating");
-        let res = Data::new().save();
+        s
+    };
 
-        assert!(res.is_ok(), "Failed to generate data. Please check that {:?} is accessible by your user. Error was: {}",
-                Data::data_folder().yellow(), err.yellow());
+    let layout = LinearLayout::vertical()
+        .child(sv.scrollable())
+        .child(TextView::new(controls).align(Align::bot_right()));
 
-        data = Data::load();
-    }
+    let view = OnEventView::new(
+        Panel::new(layout).title(format!("Reading History, last {HISTORY_DAYS} days")),
+    )
+    .on_event(Key::Esc, |s| {
+        s.pop_layer();
+    })
+    .on_action(Action::ClearHistory, clear_history)
+    .on_action(Action::ExportHistory, |s| {
+        let path = Data::data_folder().join(format!(
+            "history-{}.csv",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+
+        if let Err(e) = Data::history().and_then(|x| history::export(&x, &path)) {
+            error_panel(s, &format!("Failed to export the reading history: {e}"));
+            error!("Failed to export the reading history: {e}");
+            return;
+        }
 
-    let data = data.expect("99.99% guaranteed to succeed here");
+        info!("exported reading history to {}", path.display());
+        info_panel(
+            s,
+            &format!("Exported the reading history to {}", path.display()),
+        );
+    });
 
-    let mut main_view = LinearLayout::vertical();
+    siv.add_layer(view);
+}
 
-    main_view.add_child(
-        TextView::new({
-            let mut s = StyledString::new();
+fn chapter_search_view(siv: &mut Cursive) {
+    info!("chapter search view");
 
-            s.append_styled("Welcome to `lncli`", Effect::Bold);
+    let input = EditView::new().on_submit(|s, query| {
+        let query = query.trim().to_owned();
 
-            s
-        })
-        .center()
-        .full_width(),
-    );
+        let hits = match cache::search(&query) {
+            Ok(hits) => hits,
+            Err(e) => {
+                error_panel(s, &format!("Failed to search the saved chapters: {e}"));
+                error!("Failed to search the saved chapters: {e}");
+                return;
+            }
+        };
 
-    let mut tracked_count = StyledString::new();
+        if hits.is_empty() {
+            error_panel(s, "Nothing in the saved chapters matches.");
+            return;
+        }
 
-    tracked_count.append_plain("There are currently ");
-    tracked_count.append_styled(
-        format!("{}", data.tracked().len()),
-        Color::Dark(BaseColor::Yellow),
-    );
-    tracked_count.append_plain(" tracked novels.");
+        s.call_on_name("chapter_hits", |sv: &mut SelectView<Hit>| {
+            sv.clear();
 
-    main_view.add_child(TextView::new(tracked_count).center().full_width());
-    main_view.add_child(
-        TextView::new(
-            "The `update check` will also add an asterisk if you aren't caught up, so be aware.",
-        )
-        .center()
-        .full_width(),
-    );
+            for hit in hits {
+                let mut label = StyledString::new();
 
-    let submit = |s: &mut Cursive, novel: &LN| {
-        let ch = format!("/chapter-{}.html", novel.last_chapter);
-        load_url(s, &novel.url.replace(".html", &ch));
-        reader_view(s);
-    };
+                label.append_styled(&hit.name, Color::Dark(BaseColor::Green));
+                label.append_plain(" - ");
+                label.append_styled(format!("{}", hit.chapter), Color::Dark(BaseColor::Yellow));
+                label.append_plain(format!(" {}\n  ", hit.title));
+                label.append_plain(&hit.snippet.0);
+                label.append_styled(&hit.snippet.1, Effect::Reverse);
+                label.append_plain(&hit.snippet.2);
 
-    let tv = create_tv(&data, updates, submit);
+                sv.add_item(label, hit);
+            }
+        });
+        s.focus_name("chapter_hits").ok();
+    });
 
-    let tracked_panel = Panel::new(tv).title("Tracked Novels").full_screen();
+    let mut sv = SelectView::<Hit>::new();
 
-    let rv = create_rv(&data, submit);
+    sv.set_on_submit(|s, hit: &Hit| {
+        let query = s
+            .call_on_name(TEXT_INPUTS[4], |x: &mut EditView| x.get_content())
+            .unwrap_or_default();
 
-    let recent_panel = Panel::new(rv).title("Recent Novels").full_screen();
+        open_hit(s, query.trim(), hit);
+    });
 
-    main_view.add_child(
-        LinearLayout::horizontal()
-            .child(tracked_panel)
-            .child(recent_panel),
-    );
+    let controls = {
+        let mut s = StyledString::new();
 
-    let controls_text = get_home_controls();
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to search or open");
 
-    main_view.add_child(TextView::new(controls_text).align(Align::bot_right()));
+        s
+    };
 
-    let main_view = OnEventView::new(main_view)
-        .on_event('r', reader_view)
-        .on_event('s', |s| {
-            search_view(s, None);
-        })
-        .on_event('u', |s| {
-            update_check(s);
-        });
+    let layout = LinearLayout::vertical()
+        .child(input.with_name(TEXT_INPUTS[4]))
+        .child(DummyView)
+        .child(sv.with_name("chapter_hits").scrollable().full_height())
+        .child(TextView::new(controls).align(Align::bot_right()));
+
+    let view = OnEventView::new(
+        Panel::new(layout)
+            .title("Find in Saved Chapters")
+            .full_screen(),
+    )
+    .on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
 
-    siv.add_fullscreen_layer(main_view.full_height());
+    siv.add_layer(view);
 }
 
-fn get_home_controls() -> StyledString {
-    let mut text = StyledString::new();
+/// open the saved copy of a chapter, at the match
+fn open_hit(siv: &mut Cursive, query: &str, hit: &Hit) {
+    let chapter = match cache::load(&hit.id, hit.chapter) {
+        Ok(chapter) => chapter,
+        Err(e) => {
+            error_panel(siv, &format!("Failed to open the saved chapter: {e}"));
+            error!("Failed to open the saved chapter: {e}");
+            return;
+        }
+    };
 
-    text.append_styled("q", Color::Dark(BaseColor::Yellow));
-    text.append_plain("uit, ");
-    text.append_styled("r", Color::Dark(BaseColor::Yellow));
-    text.append_plain("eader, ");
-    text.append_styled("t", Color::Dark(BaseColor::Yellow));
-    text.append_plain("rack/untrack, ");
-    text.append_styled("s", Color::Dark(BaseColor::Yellow));
-    text.append_plain("earch, ");
-    text.append_styled("u", Color::Dark(BaseColor::Yellow));
-    text.append_plain("pdate check, ");
-    text.append_styled("enter", Color::Dark(BaseColor::Yellow));
-    text.append_plain(" to select, ");
-    text.append_styled("arrow keys", Color::Dark(BaseColor::Yellow));
-    text.append_plain(" to navigate");
+    let state = State {
+        find: Some(Find {
+            query: query.to_owned(),
+            current: hit.occurrence,
+        }),
+        ..State::from_output(&chapter.url, chapter.output)
+    };
 
-    text
+    siv.pop_layer();
+    open_chapter(siv, state);
+    reader_view(siv);
+
+    // the reader has to know its size before it can scroll to the match
+    let size = siv.screen_size();
+    siv.screen_mut().layout(size);
+
+    show_matches(siv, None);
 }
 
-fn create_rv(data: &Data, submit: fn(&mut Cursive, &LN)) -> OnEventView<NamedView<SelectView<LN>>> {
-    let label = |name: &str, last_chapter: usize| {
+fn clear_history(siv: &mut Cursive) {
+    let text = {
         let mut s = StyledString::new();
 
-        s.append_styled(
-            name,
-            Style::merge(&[Color::Dark(BaseColor::Red).into(), Effect::Bold.into()]),
-        );
-        s.append_plain(" (");
-        s.append_styled(
-            format!("{last_chapter}"),
-            Style::merge(&[Color::Dark(BaseColor::Magenta).into(), Effect::Bold.into()]),
-        );
-        s.append_plain(")");
+        s.append_plain("Clear the whole reading history? Press ");
+        s.append_styled("y", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to confirm, ");
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to cancel");
 
         s
     };
 
-    let rv = {
-        let mut sv = SelectView::new();
-        for x in data.recent() {
-            sv.add_item(label(&x.name, x.last_chapter), x.clone());
-        }
+    let view = OnEventView::new(Panel::new(TextView::new(text).center()).title("Clear History"))
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        })
+        .on_event('y', |s| {
+            s.pop_layer();
 
-        sv.set_on_submit(submit);
+            if let Err(e) = Data::clear_history() {
+                error_panel(s, &format!("Failed to clear the reading history: {e}"));
+                error!("Failed to clear the reading history: {e}");
+                return;
+            }
 
-        sv.h_align(HAlign::Center).with_name("recent_view")
-    };
+            // the history view is below us and is now stale
+            s.pop_layer();
+        });
 
-    let data = data.clone();
+    siv.add_layer(view);
+}
 
-    OnEventView::new(rv).on_event('t', move |s| {
-        let mut data = data.clone();
-        let rv = s.find_name::<SelectView<LN>>("recent_view");
+fn stats_view(siv: &mut Cursive) {
+    info!("stats view");
 
-        if rv.is_none() {
+    let history = match Data::history() {
+        Ok(history) => history,
+        Err(e) => {
+            error_panel(siv, &format!("Failed to load the reading history: {e}"));
             return;
         }
-        let rv = rv.expect("Failed to find recent view");
+    };
 
-        let selected = rv.selected_id();
+    let tracked = Data::load()
+        .map(|x| x.tracked().clone())
+        .unwrap_or_default();
 
-        if selected.is_none() {
-            return;
-        }
+    let today = stats::today();
+    let stats = Stats::new(&history, &tracked, today);
 
-        let selected = selected.expect("Failed to get selected item");
-        let item = rv.get_item(selected).expect("Failed to get selected item");
+    let layout = LinearLayout::vertical()
+        .child(TextView::new(stats_info(&stats, today)).scrollable())
+        .child(
+            TextView::new({
+                let mut s = StyledString::new();
 
-        data.add_tracked(item.1.clone());
+                s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+                s.append_plain(" to go back");
 
-        if let Err(e) = data.save() {
-            error_panel(s, &format!("Failed to save data: {e}"));
-            error!("Failed to save data: {}", e);
-        }
+                s
+            })
+            .align(Align::bot_right()),
+        );
 
-        home_view(s, &None);
-    })
+    let view =
+        OnEventView::new(Panel::new(layout).title("Reading Stats")).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+
+    siv.add_layer(view);
 }
 
-fn create_tv(
-    data: &Data,
-    updates: &Option<Vec<LN>>,
-    submit: fn(&mut Cursive, &LN),
-) -> OnEventView<NamedView<SelectView<LN>>> {
-    let label = |name: &str, last_chapter: usize, update: bool| {
-        let mut s = StyledString::new();
+fn stats_info(stats: &Stats, today: chrono::NaiveDate) -> StyledString {
+    let number = |s: &mut StyledString, n: String| {
+        s.append_styled(n, Color::Dark(BaseColor::Yellow));
+    };
+    let totals = |s: &mut StyledString, totals: &Totals| {
+        number(s, format!("{:>4}", totals.chapters));
+        s.append_plain(" chapters, ");
+        number(s, format!("{:>7}", totals.words));
+        s.append_plain(" words, ");
+        number(
+            s,
+            format!("{:>8}", history::format_duration(totals.seconds)),
+        );
+        s.append_plain("\n");
+    };
 
-        if update {
-            s.append_styled(
-                "*",
-                Style::merge(&[Color::Dark(BaseColor::Magenta).into(), Effect::Bold.into()]),
-            );
-            s.append_plain(" ");
-        }
+    let mut s = StyledString::new();
 
-        s.append_styled(
-            name,
-            Style::merge(&[Color::Dark(BaseColor::Red).into(), Effect::Bold.into()]),
-        );
-        s.append_plain(" (");
-        s.append_styled(
-            format!("{last_chapter}"),
-            Style::merge(&[Color::Dark(BaseColor::Magenta).into(), Effect::Bold.into()]),
-        );
-        s.append_plain(")");
+    s.append_styled("Total\n", Effect::Bold);
+    totals(&mut s, &stats.total);
 
-        s
-    };
+    s.append_plain("Streak: ");
+    number(&mut s, format!("{}", stats.current_streak));
+    s.append_plain(" days, longest ");
+    number(&mut s, format!("{}", stats.longest_streak));
+    s.append_plain(" days\n");
 
-    let updates = updates.clone();
-    let tv = {
-        let mut sv = SelectView::new();
-        for x in data.tracked() {
-            if let Some(updates) = &updates {
-                if updates.iter().any(|u| u.name == x.name) {
-                    sv.add_item(label(&x.name, x.last_chapter, true), x.clone());
-                } else {
-                    sv.add_item(label(&x.name, x.last_chapter, false), x.clone());
-                }
-            } else {
-                sv.add_item(label(&x.name, x.last_chapter, false), x.clone());
-            }
-        }
+    s.append_plain("Speed: ");
+    match stats.words_per_minute {
+        Some(wpm) => number(&mut s, format!("{wpm}")),
+        None => s.append_plain("?"),
+    }
+    s.append_plain(" words a minute, ");
+    match stats.minutes_per_chapter {
+        Some(minutes) => number(&mut s, format!("{minutes}")),
+        None => s.append_plain("?"),
+    }
+    s.append_plain(" minutes a chapter\n");
 
-        sv.set_on_submit(submit);
+    s.append_styled("\nLast 7 days\n", Effect::Bold);
+    for (day, day_totals) in stats.last_days(today, 7) {
+        s.append_plain(day.format("%a %m-%d  ").to_string());
+        totals(&mut s, &day_totals);
+    }
 
-        sv.h_align(HAlign::Center).with_name("tracked_view")
-    };
+    s.append_styled("\nLast 4 weeks\n", Effect::Bold);
+    for (week, week_totals) in stats.last_weeks(today, 4) {
+        s.append_plain(week.format("%m-%d      ").to_string());
+        totals(&mut s, &week_totals);
+    }
 
-    let data = data.clone();
+    s.append_styled("\nTracked novels\n", Effect::Bold);
+    for novel in &stats.novels {
+        s.append_styled(&novel.name, Color::Dark(BaseColor::Green));
+        s.append_plain(" - ");
+        number(&mut s, format!("{}", novel.chapter));
+        s.append_plain("/");
+
+        match (novel.max_chapters, novel.percent()) {
+            (Some(max), Some(percent)) => {
+                number(&mut s, format!("{max}"));
+                s.append_plain(" (");
+                number(&mut s, format!("{percent}%"));
+                s.append_plain(")\n");
+            }
+            _ => s.append_plain("?\n"),
+        }
+    }
 
-    OnEventView::new(tv).on_event('t', move |s| {
-        let updates = &updates;
-        let mut data = data.clone();
-        let sv = s.find_name::<SelectView<LN>>("tracked_view");
+    s
+}
+
+/// pick the source to move a tracked novel to
+fn migrate_view(siv: &mut Cursive, novel: &LN) {
+    info!("migrate view");
 
-        if sv.is_none() {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            error_panel(siv, &e.to_string());
             return;
         }
-        let sv = sv.expect("Failed to get select view");
+    };
 
-        let selected = sv.selected_id();
+    let sources = migrate::other_sources(&config, novel);
 
-        if selected.is_none() {
-            return;
+    match sources.as_slice() {
+        [] => {
+            error_panel(
+                siv,
+                &format!(
+                    "There are no other sources to migrate to. Add them to `sources` in {}",
+                    Config::path().display()
+                ),
+            );
         }
+        [source] => migrate_candidates(siv, novel, source),
+        sources => {
+            let mut sv = SelectView::new().h_align(HAlign::Center);
 
-        let selected = selected.expect("Failed to get selected item");
-        let item = sv
-            .get_item(selected)
-            .expect("Failed to get selected item from SelectView");
+            for source in sources {
+                sv.add_item(source.clone(), source.clone());
+            }
 
-        data.tracked_mut().retain(|x| x != item.1);
+            let novel = novel.clone();
 
-        if let Err(e) = data.save() {
-            error_panel(s, &format!("Failed to save data: {e}"));
-            error!("Failed to save data: {}", e);
-        }
+            sv.set_on_submit(move |s, source: &String| {
+                s.pop_layer();
+                migrate_candidates(s, &novel, source);
+            });
 
-        home_view(s, updates);
-    })
-}
+            let view = OnEventView::new(Panel::new(sv.scrollable()).title("Migrate To")).on_event(
+                Key::Esc,
+                |s| {
+                    s.pop_layer();
+                },
+            );
 
-fn update_check(cursive: &mut Cursive) {
-    info!("update check");
+            siv.add_layer(view);
+        }
+    }
+}
 
-    let data = Data::load();
+/// search `source` for the novel, and move it to the chosen result
+fn migrate_candidates(siv: &mut Cursive, novel: &LN, source: &str) {
+    let candidates = match migrate::candidates(source, novel) {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            error_panel(siv, &format!("Failed to search {source}: {e}"));
+            error!("Failed to search {source}: {e}");
+            return;
+        }
+    };
 
-    if data.is_err() {
-        error_panel(cursive, "Failed to load data");
+    if candidates.is_empty() {
+        error_panel(
+            siv,
+            &format!("Found nothing called {} on {source}", novel.name),
+        );
         return;
     }
 
-    let data = data.expect("Failed to load data");
+    let mut sv = SelectView::new();
 
-    let mut found = Vec::new();
+    for candidate in candidates {
+        let mut label = StyledString::new();
 
-    for x in data.tracked() {
-        let check = scrape::update_check(&x.url, x.last_chapter);
+        label.append_styled(&candidate.title, Color::Dark(BaseColor::Green));
 
-        check.map_or_else(
-            |ch| {
-                error_panel(cursive, &format!("Failed to check for updates: {ch}"));
-            },
-            |check| {
-                if check {
-                    found.push(x.clone());
-                }
-            },
+        if let Some(author) = &candidate.author {
+            label.append_plain(format!(" by {author}"));
+        }
+
+        label.append_plain(" - ");
+        label.append_styled(
+            candidate
+                .latest_chapter
+                .map_or_else(|| "?".to_owned(), |x| x.to_string()),
+            Color::Dark(BaseColor::Yellow),
         );
+        label.append_plain(" chapters");
+
+        sv.add_item(label, candidate);
     }
 
-    home_view(cursive, &Some(found));
-}
+    let title = format!("Migrate {}", novel.name);
+    let novel = novel.clone();
 
-fn load_url(siv: &mut Cursive, url: &str) {
-    info!("LOAD_URL: Attempting to load: {}", url.green());
-    let output = scrape::load(url);
+    sv.set_on_submit(move |s, candidate: &SearchResult| {
+        let title = migrate::current_chapter_title(&novel);
+        let chapter = migrate::map_chapter(&novel, title.as_deref(), candidate);
 
-    if let Err(e) = output {
-        error_panel(siv, &e.to_string());
-        panic!("{}", e);
-    }
+        let res = Data::load()
+            .and_then(|mut data| migrate::migrate(&mut data, &novel, candidate, chapter));
 
-    let output = output.expect("Failed to get output");
+        s.pop_layer();
 
-    let state = State::from_output(url, output.clone());
+        if let Err(e) = res {
+            error_panel(s, &format!("Failed to migrate {}: {e}", novel.name));
+            error!("Failed to migrate {}: {}", novel.name, e);
+            return;
+        }
 
-    info!(
-        "LOAD_URL: Successfully loaded state from url {}",
-        &state.url,
-    );
+        home_view(s, None);
+        info_panel(
+            s,
+            &format!(
+                "Moved {} to {} at chapter {chapter}",
+                novel.name, candidate.url
+            ),
+        );
+    });
 
-    let data = Data::load();
+    let controls = {
+        let mut s = StyledString::new();
 
-    let mut data = if let Err(e) = data {
-        if e.to_string().starts_with("data file does not exist") {
-            Data::new()
-        } else {
-            panic!("{}", e);
-        }
-    } else {
-        data.expect("Failed to load data")
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to migrate, keeping your progress");
+
+        s
     };
 
-    data.recent_mut().push_front(LN {
-        name: output.name.clone(),
-        url: {
-            // ugly ass hack
-            let a = url.split_once("/chapter").expect("Failed to split url");
-            let mut a = a.0.to_string();
-            a.push_str(".html");
+    let layout = LinearLayout::vertical()
+        .child(sv.scrollable())
+        .child(TextView::new(controls).align(Align::bot_right()));
 
-            a
-        },
-        last_chapter: output.chapter,
+    let view = OnEventView::new(Panel::new(layout).title(title)).on_event(Key::Esc, |s| {
+        s.pop_layer();
     });
 
-    let test_url = url
-        .split_at(
-            url.find("chapter-")
-                .expect("Failed to find 'chapter-' in url"),
-        )
-        .0;
-    let test_url = test_url[0..test_url.len() - 1].to_owned();
-    let test_url = test_url + ".html";
+    siv.add_layer(view);
+}
 
-    info!("test_url is: {}", test_url);
+/// pick a theme, previewing each one as it's selected
+fn theme_view(siv: &mut Cursive) {
+    info!("theme view");
 
-    let test_url = &test_url;
+    let previous = siv.current_theme().clone();
+    let current = Config::load().ok().and_then(|x| x.theme);
 
-    if data.tracked().iter().any(|x| x.url.starts_with(test_url)) {
-        info!("found tracked novel, updating latest chapter read");
+    let mut sv = SelectView::new().h_align(HAlign::Center);
 
-        data.tracked_mut()
-            .iter_mut()
-            .filter(|x| x.url.starts_with(test_url))
-            .for_each(|x| {
-                x.last_chapter = output.chapter;
-            });
+    for name in theme::names() {
+        sv.add_item(name.clone(), name);
     }
 
-    let save_res = data.save();
-
-    if let Err(e) = save_res {
-        panic!("{}", e);
+    if let Some(i) = current.and_then(|current| sv.iter().position(|x| *x.1 == current)) {
+        sv.set_selection(i);
     }
 
-    siv.set_user_data(state);
-}
-
-fn search_view(siv: &mut Cursive, results: Option<Search>) {
-    let results_mode = results.is_some();
-    let search_box = {
-        let ev = EditView::new().on_submit(move |s, text| {
-            let text = text.trim();
-
-            if text.is_empty() {
-                error_panel(s, "please enter a search term");
-                return;
+    sv.set_on_select(|s, name: &String| {
+        let status = match theme::load(name) {
+            Ok(theme) => {
+                s.set_theme(theme);
+                StyledString::new()
             }
+            Err(e) => StyledString::styled(e.to_string(), Color::Dark(BaseColor::Red)),
+        };
 
-            if text.len() < 3 {
-                error_panel(s, "please enter at least 3 characters");
-                return;
-            }
+        s.call_on_name("theme_status", |x: &mut TextView| x.set_content(status));
+    });
 
-            search_url(s, text);
+    sv.set_on_submit(|s, name: &String| {
+        let res = theme::load(name).and_then(|theme| {
+            Config::set(None, "theme", name.as_str())?;
+            Ok(theme)
         });
 
-        if let Some(results) = &results {
-            ev.content(&results.query)
-        } else {
-            ev
+        match res {
+            Ok(theme) => {
+                s.set_theme(theme);
+                s.pop_layer();
+            }
+            Err(e) => {
+                error_panel(s, &e.to_string());
+                error!("Failed to set the theme: {e}");
+            }
         }
-    };
-
-    let search_results = { results.as_ref().map(create_sv) };
+    });
 
-    let search_layout = {
-        let mut layout = LinearLayout::vertical();
+    let controls = {
+        let mut s = StyledString::new();
 
-        if let Some(search_results) = search_results {
-            let query = results
-                .expect("Failed to get query from search results")
-                .query;
-            let query = query.trim();
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back, ");
+        s.append_styled("enter", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to keep the theme");
 
-            layout.add_child(
-                TextView::new({
-                    let mut s = StyledString::new();
+        s
+    };
 
-                    s.append_styled(query, Color::Dark(BaseColor::Green));
+    let layout = LinearLayout::vertical()
+        .child(sv.scrollable())
+        .child(TextView::new("").with_name("theme_status"))
+        .child(TextView::new(controls).align(Align::bot_right()));
 
-                    s
-                })
-                .h_align(HAlign::Center)
-                .no_wrap(),
-            );
-            layout.add_child(
-                TextView::new("-".repeat(query.len() + 2))
-                    .h_align(HAlign::Center)
-                    .no_wrap(),
-            );
+    let view = OnEventView::new(Panel::new(layout).title("Themes")).on_event(Key::Esc, move |s| {
+        s.set_theme(previous.clone());
+        s.pop_layer();
+    });
 
-            layout.add_child(search_results);
-        } else {
-            layout.add_child(search_box);
-        }
+    siv.add_layer(view);
+}
 
-        layout
-    };
+fn info_panel(siv: &mut Cursive, text: &str) {
+    let layout = LinearLayout::vertical()
+        .child(TextView::new(text).center())
+        .child(
+            TextView::new({
+                let mut s = StyledString::new();
 
-    let results_mode_text = {
-        let mut s = StyledString::new();
+                s.append_plain("Press ");
+                s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+                s.append_plain(" to close");
 
-        if results_mode {
-            s.append_styled("t", Color::Dark(BaseColor::Yellow));
-            s.append_plain("rack, ");
-            s.append_styled("esc", Color::Dark(BaseColor::Yellow));
-            s.append_plain(" to go back, ");
-            s.append_styled("enter", Color::Dark(BaseColor::Yellow));
-            s.append_plain(" to select, ");
-            s.append_styled("arrow keys", Color::Dark(BaseColor::Yellow));
-            s.append_plain(" to navigate");
-        } else {
-            s.append_styled("esc", Color::Dark(BaseColor::Yellow));
-            s.append_plain(" to go back, ");
-            s.append_styled("enter", Color::Dark(BaseColor::Yellow));
-            s.append_plain(" to search");
-        }
+                s
+            })
+            .center(),
+        );
 
-        s
-    };
+    let panel = OnEventView::new(Panel::new(layout)).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
 
-    let controls = TextView::new(results_mode_text).align(Align::bot_right());
+    siv.add_layer(panel);
+}
 
-    let layout = LinearLayout::new(Orientation::Vertical)
-        .child(search_layout)
-        .child(controls);
+fn details_view(siv: &mut Cursive, url: &str) {
+    info!("details view: {}", url.green());
 
-    let panel = Panel::new(layout).title(if results_mode {
-        "Search Results"
-    } else {
-        "Search"
-    });
+    let details = scrape::details(url);
 
-    let view = OnEventView::new(panel).on_event(Key::Esc, |s| {
-        s.pop_layer();
-        s.pop_layer();
+    let details = match details {
+        Ok(details) => details,
+        Err(e) => {
+            error_panel(siv, &format!("Failed to load novel details: {e}"));
+            error!("Failed to load novel details: {e}");
+            return;
+        }
+    };
 
-        home_view(s, &None);
+    let progress = Data::load().ok().and_then(|data| {
+        data.tracked()
+            .iter()
+            .chain(data.recent().iter())
+            .filter(|x| x.is_at(&details.url))
+            .map(|x| x.last_chapter)
+            .max()
     });
 
-    siv.add_layer(view);
-}
+    let info = details_info(&details, progress);
 
-fn create_sv(search: &Search) -> cursive::views::OnEventView<cursive::views::SelectView<url::Url>> {
-    let mut sv = SelectView::new().h_align(HAlign::Center);
+    let synopsis = TextView::new(&details.synopsis).scrollable();
 
-    if search.results.is_empty() {
-        sv.set_on_submit(|s, _| {
-            s.pop_layer();
-            error_panel(s, "No results found.\nTry a shorter query.");
-        });
-        return OnEventView::new(sv);
-    }
+    let controls = {
+        let mut s = StyledString::new();
 
-    for (url, label) in search.results.clone() {
-        sv.add_item(label, url);
-    }
+        keys::hint(&mut s, Action::ReadFromStart, "read from start, ");
+        if progress.is_some() {
+            keys::hint(&mut s, Action::Continue, "continue, ");
+        }
+        keys::hint(&mut s, Action::Track, "track, ");
+        keys::hint(&mut s, Action::OpenInBrowser, "Open in browser, ");
+        s.append_styled("esc", Color::Dark(BaseColor::Yellow));
+        s.append_plain(" to go back");
 
-    sv.set_on_submit(|s, url| {
-        let url = url.as_str();
+        s
+    };
 
-        let url = url.replace(".html", "/chapter-1.html");
+    let layout = LinearLayout::vertical()
+        .child(TextView::new(info))
+        .child(Panel::new(synopsis).title("Synopsis").full_height())
+        .child(TextView::new(controls).align(Align::bot_right()));
 
-        load_url(s, &url);
+    let panel = Panel::new(layout).title("Novel Details").full_screen();
+
+    let read = |s: &mut Cursive, url: &str, chapter: usize| {
+        let ch = format!("/chapter-{chapter}.html");
+        load_url(s, &url.replace(".html", &ch));
         s.pop_layer();
         reader_view(s);
-    });
-
-    let selected = Arc::new(RwLock::new(Some(search.results[0].0.to_string())));
-
-    let si = selected.clone();
-    sv.set_on_select(move |_, url| {
-        let url = url.as_str();
+    };
 
-        si.write_arc().replace(url.to_owned());
-    });
+    let (d1, d2, d3, d4) = (details.clone(), details.clone(), details.clone(), details);
 
-    OnEventView::new(sv).on_event('t', move |s| {
-        let sel = {
-            let selected = selected.clone().read_arc();
-            if selected.is_none() {
-                info!("Selected was none");
+    let view = OnEventView::new(panel)
+        .on_action(Action::ReadFromStart, move |s| {
+            read(s, &d1.url, 1);
+        })
+        .on_action(Action::Continue, move |s| {
+            let Some(progress) = progress else {
+                error_panel(s, "You haven't started reading this novel yet.");
                 return;
-            }
-            selected.clone().expect("No selected value found")
-        };
+            };
 
-        info!("Selected: {}", sel.green());
+            read(s, &d2.url, progress);
+        })
+        .on_action(Action::Track, move |s| {
+            let data = Data::load();
 
-        let data = Data::load();
+            let Ok(mut data) = data else {
+                error_panel(s, "Failed to load data");
+                return;
+            };
 
-        if let Ok(mut data) = data {
             data.add_tracked(LN {
-                name: scrape::get_name(&sel).expect("Failed to get name from selection"),
-                url: sel.clone(),
-                last_chapter: 1,
+                status: d3.status,
+                ..LN::new(d3.name.clone(), d3.url.clone(), progress.unwrap_or(1))
             });
 
-            let data = data.save();
-
-            if let Err(e) = data {
-                error_panel(s, &e.to_string());
+            if let Err(e) = data.save() {
+                error_panel(s, &format!("Failed to save data: {e}"));
+                error!("Failed to save data: {e}");
             }
-        }
-    })
+        })
+        .on_action(Action::OpenInBrowser, move |s| {
+            let res = open::that(&d4.url);
+
+            let Err(e) = res else {
+                return;
+            };
+
+            error_panel(s, "Could not open the url.");
+            error!("Could not open the url: {e}");
+        })
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        });
+
+    siv.add_layer(view);
 }
 
-fn search_url(siv: &mut Cursive, query: &str) {
-    info!("search query: {}", query.green());
+fn details_info(details: &NovelDetails, progress: Option<usize>) -> StyledString {
+    let field = |s: &mut StyledString, label: &str, value: &str| {
+        s.append_styled(format!("{label}: "), Effect::Bold);
+        s.append_plain(value);
+        s.append_plain("\n");
+    };
 
-    let output = scrape::search(query);
+    let mut s = StyledString::new();
 
-    siv.pop_layer();
-    if let Err(e) = &output {
-        search_view(siv, None);
-        error_panel(siv, &e.to_string());
-        return;
-    }
+    s.append_styled(
+        &details.name,
+        Style::merge(&[Color::Dark(BaseColor::Green).into(), Effect::Bold.into()]),
+    );
+    s.append_plain("\n\n");
 
-    let output = output.expect("Failed to get search results");
+    if !details.alt_titles.is_empty() {
+        field(&mut s, "Also known as", &details.alt_titles.join(", "));
+    }
+    field(
+        &mut s,
+        "Author",
+        details.author.as_deref().unwrap_or("Unknown"),
+    );
+    field(&mut s, "Genres", &details.genres.join(", "));
+    field(&mut s, "Status", &details.status.to_string());
+    field(&mut s, "Chapters", &details.chapters.to_string());
+    if let Some(progress) = progress {
+        field(&mut s, "Last read", &progress.to_string());
+    }
+    if let Some(cover) = &details.cover {
+        field(&mut s, "Cover", cover);
+    }
 
-    search_view(siv, Some(output));
+    s
 }
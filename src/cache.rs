@@ -0,0 +1,209 @@
+//! a copy of every chapter that was read, and an index of the words in them for searching
+//!
+//! chapters are kept as `chapters/<novel id>/<chapter>.json` in the data folder, and the index
+//! as `chapters/index.jsonl`, one line with the words of each chapter, so adding one is an append
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::{
+    data::{novel_id, Data},
+    reader,
+    scrape::Output,
+    Res,
+};
+
+/// the most results a search gives, so a common word doesn't list the whole library
+const MAX_HITS: usize = 500;
+
+/// how many characters of the paragraph to show on either side of a match
+const SNIPPET_CONTEXT: usize = 40;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Chapter {
+    pub url: String,
+    #[serde(flatten)]
+    pub output: Output,
+}
+
+/// the words in one cached chapter
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct IndexEntry {
+    id: String,
+    chapter: usize,
+    /// lowercase, without repeats, sorted
+    words: Vec<String>,
+}
+
+/// a place in a cached chapter where the search was found
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub id: String,
+    pub name: String,
+    pub chapter: usize,
+    pub title: String,
+    /// the paragraph around the match, split into what comes before it, the match, and after
+    pub snippet: (String, String, String),
+    /// how many matches in the chapter come before this one
+    pub occurrence: usize,
+}
+
+fn folder() -> PathBuf {
+    Data::data_folder().join("chapters")
+}
+
+fn index_file() -> PathBuf {
+    folder().join("index.jsonl")
+}
+
+fn chapter_file(id: &str, chapter: usize) -> PathBuf {
+    folder().join(id).join(format!("{chapter}.json"))
+}
+
+/// the lowercase words in `text`, for the index and for searching it
+fn words(text: &str) -> Vec<String> {
+    let mut words = text
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+
+    words.sort_unstable();
+    words.dedup();
+
+    words
+}
+
+/// keep a copy of a chapter, adding it to the index when it's new or its text changed
+pub fn store(url: &str, output: &Output) -> Res<()> {
+    let id = novel_id(url);
+    let path = chapter_file(&id, output.chapter);
+    let changed = load(&id, output.chapter).map_or(true, |x| x.output.content != output.content);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(
+        &path,
+        serde_json::to_string(&Chapter {
+            url: url.to_owned(),
+            output: output.clone(),
+        })?,
+    )?;
+
+    // the last entry for a chapter is the one that counts, so a changed chapter is just appended again
+    if changed {
+        let entry = IndexEntry {
+            id,
+            chapter: output.chapter,
+            words: words(&output.content),
+        };
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(index_file())?;
+
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    }
+
+    Ok(())
+}
+
+/// a cached chapter
+pub fn load(id: &str, chapter: usize) -> Res<Chapter> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(
+        chapter_file(id, chapter),
+    )?)?)
+}
+
+/// every place `query` is in the cached chapters, by novel and then chapter
+pub fn search(query: &str) -> Res<Vec<Hit>> {
+    let query = query.trim();
+    let wanted = words(query);
+    let path = index_file();
+
+    if wanted.is_empty() || !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    // a chapter that changed has a newer entry further down, which replaces the older one
+    let latest = std::fs::read_to_string(path)?
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .filter_map(|line| {
+            serde_json::from_str::<IndexEntry>(line)
+                .map_err(|e| error!("skipping unreadable index entry: {e}"))
+                .ok()
+        })
+        .map(|x| ((x.id.clone(), x.chapter), x))
+        .collect::<HashMap<_, _>>();
+
+    // only chapters with every word in them can have the whole query
+    let candidates = latest
+        .into_values()
+        .filter(|x| wanted.iter().all(|w| x.words.binary_search(w).is_ok()))
+        .collect::<Vec<_>>();
+
+    let mut hits = Vec::new();
+
+    for entry in candidates {
+        let chapter = match load(&entry.id, entry.chapter) {
+            Ok(chapter) => chapter,
+            Err(e) => {
+                error!(
+                    "skipping cached chapter {} of {}: {e}",
+                    entry.chapter, entry.id
+                );
+                continue;
+            }
+        };
+
+        let content = &chapter.output.content;
+
+        for (occurrence, found) in reader::find_in(content, query).into_iter().enumerate() {
+            hits.push(Hit {
+                id: entry.id.clone(),
+                name: chapter.output.name.clone(),
+                chapter: chapter.output.chapter,
+                title: chapter.output.chapter_title.clone(),
+                snippet: snippet(content, found.start, found.end),
+                occurrence,
+            });
+        }
+    }
+
+    hits.sort_by(|a, b| {
+        (&a.name, a.chapter, a.occurrence).cmp(&(&b.name, b.chapter, b.occurrence))
+    });
+    hits.truncate(MAX_HITS);
+
+    Ok(hits)
+}
+
+/// the paragraph around `start..end`, cut down to a little on either side
+fn snippet(content: &str, start: usize, end: usize) -> (String, String, String) {
+    let paragraph_start = content[..start].rfind("\n\n").map_or(0, |x| x + 2);
+    let paragraph_end = content[end..]
+        .find("\n\n")
+        .map_or(content.len(), |x| end + x);
+
+    let before = &content[paragraph_start..start];
+    let after = &content[end..paragraph_end];
+
+    let skip = before.chars().count().saturating_sub(SNIPPET_CONTEXT);
+    let before = before.chars().skip(skip).collect::<String>();
+    let cut = after.chars().count() > SNIPPET_CONTEXT;
+    let after = after.chars().take(SNIPPET_CONTEXT).collect::<String>();
+
+    // the snippet is shown on one line
+    (
+        format!("{}{}", if skip > 0 { "…" } else { "" }, before.trim_start()).replace('\n', " "),
+        content[start..end].replace('\n', " "),
+        format!("{}{}", after.trim_end(), if cut { "…" } else { "" }).replace('\n', " "),
+    )
+}
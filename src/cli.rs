@@ -0,0 +1,166 @@
+//! the non-interactive subcommands, for everything that doesn't need the TUI
+
+use color_eyre::eyre::{bail, eyre};
+use owo_colors::OwoColorize;
+use std::path::PathBuf;
+
+use crate::{
+    config::Config,
+    data::Data,
+    sync::{self, Remote},
+    transfer::{self, Format},
+    Res,
+};
+
+const USAGE: &str = "\
+usage: lncli [command]
+
+without a command, the TUI is started.
+
+commands:
+    export <path> [--format json|csv]
+        export the tracked novels and reading progress
+    import <path> [--format json|csv|tachiyomi|urls]
+        import novels into the tracked list
+    sync [--remote <folder or git url>]
+        merge the library with the copy on the remote, `sync.remote` in the config by default
+    rewrite
+        apply the `[[rewrite]]` rules in the config to the stored urls
+    help
+        show this message
+
+the format is guessed from the file extension when not given.";
+
+pub fn run(args: &[String]) -> Res<()> {
+    let (command, args) = args
+        .split_first()
+        .ok_or_else(|| eyre!("no command given\n\n{USAGE}"))?;
+
+    match command.as_str() {
+        "export" => export(args),
+        "import" => import(args),
+        "sync" => sync(args),
+        "rewrite" => rewrite(args),
+        "help" | "-h" | "--help" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => bail!("unknown command: {}\n\n{USAGE}", command.red()),
+    }
+}
+
+/// parse `<path> [--format <format>]`
+fn transfer_args(args: &[String]) -> Res<(PathBuf, Format)> {
+    let mut path = None;
+    let mut format = None;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--format" || arg == "-f" {
+            let value = args
+                .next()
+                .ok_or_else(|| eyre!("`{arg}` needs a value\n\n{USAGE}"))?;
+
+            format = Some(value.parse::<Format>()?);
+        } else if path.is_none() {
+            path = Some(PathBuf::from(arg));
+        } else {
+            bail!("unexpected argument: {}\n\n{USAGE}", arg.red());
+        }
+    }
+
+    let path = path.ok_or_else(|| eyre!("no path given\n\n{USAGE}"))?;
+    let format = format.unwrap_or_else(|| Format::guess(&path));
+
+    Ok((path, format))
+}
+
+fn export(args: &[String]) -> Res<()> {
+    let (path, format) = transfer_args(args)?;
+
+    let data = Data::load()?;
+
+    let count = transfer::export(&data, format, &path)?;
+
+    println!(
+        "exported {} novels to {} as {}",
+        count.yellow(),
+        path.display().to_string().green(),
+        format.yellow()
+    );
+
+    Ok(())
+}
+
+fn import(args: &[String]) -> Res<()> {
+    let (path, format) = transfer_args(args)?;
+
+    let novels = transfer::import(format, &path)?;
+
+    let mut data = if Data::exists()? {
+        Data::load()?
+    } else {
+        Data::new()
+    };
+
+    let (added, updated) = transfer::merge(&mut data, novels);
+
+    data.save()?;
+
+    println!(
+        "imported {} new and updated {} tracked novels from {}",
+        added.yellow(),
+        updated.yellow(),
+        path.display().to_string().green()
+    );
+
+    Ok(())
+}
+
+fn sync(args: &[String]) -> Res<()> {
+    let remote = match args {
+        [] => None,
+        [flag, remote] if flag == "--remote" || flag == "-r" => Some(remote.clone()),
+        _ => bail!("unexpected arguments: {}\n\n{USAGE}", args.join(" ").red()),
+    };
+
+    let config = Config::load()?;
+    let remote = Remote::new(&config.sync, remote)?;
+
+    let summary = sync::sync(&remote)?;
+
+    println!(
+        "synced: {} added, {} removed, {} updated",
+        summary.added.yellow(),
+        summary.removed.yellow(),
+        summary.updated.yellow()
+    );
+
+    Ok(())
+}
+
+fn rewrite(args: &[String]) -> Res<()> {
+    if let Some(arg) = args.first() {
+        bail!("unexpected argument: {}\n\n{USAGE}", arg.red());
+    }
+
+    if Config::load()?.rewrites.is_empty() {
+        bail!(
+            "there are no `[[rewrite]]` rules in {}",
+            Config::path().display().green()
+        );
+    }
+
+    let mut data = Data::load()?;
+
+    let updated = data.update_urls();
+
+    if updated > 0 {
+        data.save()?;
+    }
+
+    println!("updated the urls of {} novels", updated.yellow());
+
+    Ok(())
+}
@@ -0,0 +1,308 @@
+//! user configuration, read from `config.toml` in the data folder
+
+use color_eyre::eyre::eyre;
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::{
+    data::Data,
+    keys::{Action, Binding, Keymap},
+    rewrite::RewriteRule,
+    Res,
+};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub sync: SyncConfig,
+    pub recent: RecentConfig,
+    /// base urls of other sites with the same layout, that novels can be migrated to
+    pub sources: Vec<String>,
+    pub reader: ReaderConfig,
+    /// the name of a theme, see [`crate::theme`]
+    pub theme: Option<String>,
+    /// the set of keybindings to start from
+    pub keymap: Keymap,
+    /// overrides for the keymap's keybindings
+    pub keys: BTreeMap<Action, Binding>,
+    /// `[[rewrite]]` tables, applied to stored urls and before every request
+    #[serde(rename = "rewrite")]
+    pub rewrites: Vec<RewriteRule>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// a folder, or the url of a git repository
+    pub remote: Option<String>,
+    /// guessed from `remote` when not set
+    pub kind: Option<RemoteKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteKind {
+    Folder,
+    Git,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RecentConfig {
+    /// how many novels the recent list keeps
+    pub size: usize,
+    pub chapter: RecentChapter,
+}
+
+impl Default for RecentConfig {
+    fn default() -> Self {
+        Self {
+            size: 10,
+            chapter: RecentChapter::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReaderConfig {
+    pub mode: ReaderMode,
+    pub align: TextAlign,
+    /// the widest the text gets, in characters. a share of the screen when not set
+    pub width: Option<usize>,
+    /// blank lines between paragraphs
+    pub paragraph_spacing: usize,
+    /// spaces before the first line of each paragraph
+    pub indent: usize,
+    /// lines per minute in auto mode
+    pub auto_scroll: usize,
+}
+
+impl Default for ReaderConfig {
+    fn default() -> Self {
+        Self {
+            mode: ReaderMode::default(),
+            align: TextAlign::default(),
+            width: None,
+            paragraph_spacing: 1,
+            indent: 0,
+            auto_scroll: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReaderMode {
+    /// the whole chapter in one scrolling view
+    #[default]
+    Scroll,
+    /// the chapter split into screen-sized pages
+    Paged,
+    /// the next chapter added below the current one when the end is reached
+    Continuous,
+    /// the whole chapter, scrolled at a steady pace
+    Auto,
+}
+
+impl ReaderMode {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Scroll => "scroll",
+            Self::Paged => "paged",
+            Self::Continuous => "continuous",
+            Self::Auto => "auto",
+        }
+    }
+
+    /// the mode after this one, going around
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Scroll => Self::Paged,
+            Self::Paged => Self::Continuous,
+            Self::Continuous => Self::Auto,
+            Self::Auto => Self::Scroll,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    Left,
+    #[default]
+    Center,
+    Justified,
+}
+
+/// which chapter the recent list shows for a novel that was opened more than once
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecentChapter {
+    /// the furthest chapter opened
+    #[default]
+    Furthest,
+    /// the chapter that was opened last, even when going back
+    LastOpened,
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        Data::data_folder().join("config.toml")
+    }
+
+    /// load the config file, falling back to the defaults if there isn't one
+    pub fn load() -> Res<Self> {
+        let path = Self::path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let config = std::fs::read_to_string(&path)?;
+
+        toml::from_str(&config).map_err(|e| {
+            eyre!(
+                "failed to parse {}: {e}",
+                path.display().to_string().green()
+            )
+        })
+    }
+
+    /// change a single setting, leaving the rest of the file (comments included) as it is.
+    /// `table` is the `[table]` the key is in, or `None` for the top of the file
+    pub fn set(table: Option<&str>, key: &str, value: impl Into<toml::Value>) -> Res<()> {
+        let path = Self::path();
+
+        let config = if path.exists() {
+            std::fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+
+        let line = format!("{key} = {}", value.into());
+        let updated = set_line(&config, table, key, &line);
+
+        // never write something that can't be read back
+        toml::from_str::<Self>(&updated).map_err(|e| {
+            eyre!(
+                "failed to update {}: {e}",
+                path.display().to_string().green()
+            )
+        })?;
+
+        std::fs::create_dir_all(Data::data_folder())?;
+        std::fs::write(path, updated)?;
+
+        Ok(())
+    }
+}
+
+/// replace the line setting `key` in `table` with `line`, or add it if there isn't one
+fn set_line(config: &str, table: Option<&str>, key: &str, line: &str) -> String {
+    let mut lines = config.lines().map(ToOwned::to_owned).collect::<Vec<_>>();
+
+    let is_header = |x: &str| x.trim_start().starts_with('[');
+    let sets_key = |x: &str| {
+        x.trim_start()
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    };
+
+    let start = table.map_or(Some(0), |table| {
+        lines
+            .iter()
+            .position(|x| x.trim() == format!("[{table}]"))
+            .map(|x| x + 1)
+    });
+
+    let Some(start) = start else {
+        if lines.last().is_some_and(|x| !x.trim().is_empty()) {
+            lines.push(String::new());
+        }
+
+        lines.push(format!("[{}]", table.unwrap_or_default()));
+        lines.push(line.to_owned());
+
+        return lines.join("\n") + "\n";
+    };
+
+    let end = lines[start..]
+        .iter()
+        .position(|x| is_header(x))
+        .map_or(lines.len(), |x| x + start);
+
+    match lines[start..end].iter().position(|x| sets_key(x)) {
+        Some(i) => line.clone_into(&mut lines[start + i]),
+        // top level keys have to come before the first table
+        None if table.is_none() => lines.insert(0, line.to_owned()),
+        None => {
+            let after = (start..end)
+                .rev()
+                .find(|&i| !lines[i].trim().is_empty())
+                .map_or(start, |i| i + 1);
+
+            lines.insert(after, line.to_owned());
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_line_replaces_the_key_and_keeps_the_rest() {
+        let config = "# my settings\n[reader]\nwidth = 60 # narrow\nwidths = 1\nmode = \"paged\"\n";
+
+        assert_eq!(
+            set_line(config, Some("reader"), "width", "width = 80"),
+            "# my settings\n[reader]\nwidth = 80\nwidths = 1\nmode = \"paged\"\n"
+        );
+    }
+
+    #[test]
+    fn set_line_adds_the_key_at_the_end_of_its_table() {
+        let config = "[reader]\nmode = \"paged\"\n\n[keys]\nquit = \"q\"\n";
+
+        assert_eq!(
+            set_line(config, Some("reader"), "indent", "indent = 2"),
+            "[reader]\nmode = \"paged\"\nindent = 2\n\n[keys]\nquit = \"q\"\n"
+        );
+    }
+
+    #[test]
+    fn set_line_adds_a_missing_table() {
+        assert_eq!(
+            set_line("", Some("reader"), "width", "width = 80"),
+            "[reader]\nwidth = 80\n"
+        );
+        assert_eq!(
+            set_line("theme = \"dark\"\n", Some("reader"), "width", "width = 80"),
+            "theme = \"dark\"\n\n[reader]\nwidth = 80\n"
+        );
+    }
+
+    #[test]
+    fn set_line_keeps_top_level_keys_out_of_tables() {
+        let config = "[reader]\ntheme = 1\n";
+
+        assert_eq!(
+            set_line(config, None, "theme", "theme = \"dark\""),
+            "theme = \"dark\"\n[reader]\ntheme = 1\n"
+        );
+        assert_eq!(
+            set_line(
+                "theme = \"light\"\n[reader]\n",
+                None,
+                "theme",
+                "theme = \"dark\""
+            ),
+            "theme = \"dark\"\n[reader]\n"
+        );
+    }
+}
@@ -1,22 +1,523 @@
-use color_eyre::eyre::bail;
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::{bail, eyre};
+use log::{error, info};
 use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cmp::Reverse;
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use url::Url;
 
-use crate::Res;
+use crate::{
+    config::{Config, RecentChapter},
+    history::HistoryEntry,
+    rewrite,
+    scrape::{novel_url, Status},
+    Res,
+};
+
+#[cfg(not(feature = "sqlite"))]
+mod json;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(not(feature = "sqlite"))]
+use json as backend;
+#[cfg(feature = "sqlite")]
+use sqlite as backend;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct LN {
+    /// stays the same when the name or url change, see [`novel_id`]
+    #[serde(default)]
+    pub id: String,
     pub name: String,
     pub url: String,
     pub last_chapter: usize,
+    #[serde(default)]
+    pub status: Status,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// when each field was last changed, used to resolve conflicts when merging
+    #[serde(default)]
+    pub stamps: Stamps,
+    /// when the novel was last opened, only kept for the recent list, which is ordered by it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opened_at: Option<DateTime<Utc>>,
+}
+
+impl LN {
+    pub fn new(name: String, url: String, last_chapter: usize) -> Self {
+        Self {
+            id: novel_id(&url),
+            name,
+            url,
+            last_chapter,
+            status: Status::Unknown,
+            tags: Vec::new(),
+            stamps: Stamps::default(),
+            opened_at: None,
+        }
+    }
+
+    /// whether `url` is this novel's page or one of its chapters. the id stays the same when the
+    /// url is rewritten, so the current url is checked too, for rewrites that change more than the domain
+    pub fn is_at(&self, url: &str) -> bool {
+        let id = novel_id(url);
+
+        self.id == id || novel_id(&self.url) == id
+    }
+
+    /// mark every field that differs from `before` as changed at `now`
+    fn stamp_changes(&mut self, before: Option<&Self>, now: DateTime<Utc>) {
+        let Some(before) = before else {
+            self.stamps = Stamps {
+                tracked: now,
+                url: now,
+                last_chapter: now,
+                status: now,
+                tags: now,
+            };
+            return;
+        };
+
+        if self.url != before.url {
+            self.stamps.url = now;
+        }
+        if self.last_chapter != before.last_chapter {
+            self.stamps.last_chapter = now;
+        }
+        if self.status != before.status {
+            self.stamps.status = now;
+        }
+        if self.tags != before.tags {
+            self.stamps.tags = now;
+        }
+    }
+
+    /// take every field of `other` that was changed more recently than ours
+    pub fn merge_newest(&mut self, other: &Self) {
+        if other.stamps.url > self.stamps.url {
+            self.url.clone_from(&other.url);
+            self.stamps.url = other.stamps.url;
+        }
+        if other.stamps.last_chapter > self.stamps.last_chapter {
+            self.last_chapter = other.last_chapter;
+            self.stamps.last_chapter = other.stamps.last_chapter;
+        }
+        if other.stamps.status > self.stamps.status {
+            self.status = other.status;
+            self.stamps.status = other.stamps.status;
+        }
+        if other.stamps.tags > self.stamps.tags {
+            self.tags.clone_from(&other.tags);
+            self.stamps.tags = other.stamps.tags;
+        }
+
+        self.stamps.tracked = self.stamps.tracked.max(other.stamps.tracked);
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Stamps {
+    pub tracked: DateTime<Utc>,
+    pub url: DateTime<Utc>,
+    pub last_chapter: DateTime<Utc>,
+    pub status: DateTime<Utc>,
+    pub tags: DateTime<Utc>,
+}
+
+/// a record of a novel being untracked, so merging doesn't bring it back
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Untracked {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub at: DateTime<Utc>,
+}
+
+/// path segments that come before a novel's slug on some sites, like the `novel` in `/novel/martial-peak`
+const SLUG_PREFIXES: &[&str] = &[
+    "novel",
+    "novels",
+    "book",
+    "books",
+    "series",
+    "novel-book",
+    "read",
+    "b",
+    "n",
+];
+
+/// labels that sit between a site's name and a country code, like the `co` in `example.co.uk`
+const SECOND_LEVEL_LABELS: &[&str] = &["co", "com", "org", "net", "ac", "gov", "edu"];
+
+/// a stable identifier for a novel, made of the site's name and the novel's slug, like `freewebnovel/martial-peak`.
+/// it's the same for the novel's page and any of its chapters, and doesn't change when the site moves to another top level domain
+pub fn novel_id(url: &str) -> String {
+    let Ok(parsed) = Url::parse(url) else {
+        return normalize_id(url);
+    };
+
+    format!(
+        "{}/{}",
+        normalize_id(&id_source(&parsed)),
+        normalize_id(&id_slug(&parsed))
+    )
+}
+
+fn normalize_id(x: &str) -> String {
+    x.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// the host without `www.` or its top level domain, `co.uk` and the like counting as one
+fn id_source(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    let mut labels = host.split('.').collect::<Vec<_>>();
+
+    if labels.len() > 1 {
+        labels.pop();
+    }
+
+    if labels.len() > 1
+        && labels
+            .last()
+            .is_some_and(|x| SECOND_LEVEL_LABELS.contains(x))
+    {
+        labels.pop();
+    }
+
+    labels.join(".")
+}
+
+/// the path segment naming the novel, out of the ones before any `chapter-N` segment
+fn id_slug(url: &Url) -> String {
+    let segments = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|x| !x.is_empty())
+        .take_while(|x| !x.to_lowercase().starts_with("chapter"))
+        .map(|x| x.strip_suffix(".html").unwrap_or(x))
+        .collect::<Vec<_>>();
+
+    segments
+        .iter()
+        .find(|x| !SLUG_PREFIXES.contains(&x.to_lowercase().as_str()))
+        .or_else(|| segments.last())
+        .map_or_else(String::new, |x| (*x).to_owned())
+}
+
+/// give novels and tombstones from before ids were stored (e.g. in old exports or sync files) their id,
+/// and repair the ones made before [`novel_id`] knew about slug prefixes and `co.uk` style domains
+pub fn fix_ids(novels: &mut [LN], untracked: &mut [Untracked]) {
+    for novel in novels {
+        novel.id = fixed_id(&novel.id, &novel.url);
+    }
+
+    for novel in untracked {
+        novel.id = fixed_id(&novel.id, &novel.url);
+    }
+}
+
+/// `id` with the parts the old [`novel_id`] got wrong for `url` worked out again.
+/// parts that came from another url, before it was rewritten, are left alone
+fn fixed_id(id: &str, url: &str) -> String {
+    if id.is_empty() {
+        return novel_id(url);
+    }
+
+    let (Some((source, slug)), Ok(parsed)) = (id.split_once('/'), Url::parse(url)) else {
+        return id.to_owned();
+    };
+
+    let (old_source, old_slug) = {
+        let host = parsed.host_str().unwrap_or_default();
+        let host = host.strip_prefix("www.").unwrap_or(host);
+
+        let slug = parsed
+            .path_segments()
+            .and_then(|mut x| x.find(|x| !x.is_empty()))
+            .unwrap_or_default();
+
+        (
+            normalize_id(host.rsplit_once('.').map_or(host, |x| x.0)),
+            normalize_id(slug.strip_suffix(".html").unwrap_or(slug)),
+        )
+    };
+
+    let source = if source == old_source {
+        normalize_id(&id_source(&parsed))
+    } else {
+        source.to_owned()
+    };
+
+    let slug = if slug == old_slug {
+        normalize_id(&id_slug(&parsed))
+    } else {
+        slug.to_owned()
+    };
+
+    format!("{source}/{slug}")
+}
+
+/// merge two tracked lists, keeping the newest change to every field.
+/// a novel stays untracked if it was untracked after it was (re)tracked on either side
+pub fn merge_tracked(
+    (ours, our_untracked): (&[LN], &[Untracked]),
+    (theirs, their_untracked): (&[LN], &[Untracked]),
+) -> (Vec<LN>, Vec<Untracked>) {
+    let mut untracked: Vec<Untracked> = Vec::new();
+
+    for x in our_untracked.iter().chain(their_untracked) {
+        if let Some(existing) = untracked.iter_mut().find(|u| u.id == x.id) {
+            if x.at > existing.at {
+                *existing = x.clone();
+            }
+        } else {
+            untracked.push(x.clone());
+        }
+    }
+
+    let mut tracked: Vec<LN> = ours.to_vec();
+
+    for novel in theirs {
+        if let Some(existing) = tracked.iter_mut().find(|x| x.id == novel.id) {
+            existing.merge_newest(novel);
+        } else {
+            tracked.push(novel.clone());
+        }
+    }
+
+    tracked.retain(|x| {
+        !untracked
+            .iter()
+            .any(|u| u.id == x.id && u.at > x.stamps.tracked)
+    });
+
+    // tombstones older than a novel being tracked again aren't needed anymore
+    untracked.retain(|u| !tracked.iter().any(|x| x.id == u.id));
+
+    (tracked, untracked)
+}
+
+/// the version of the data file this build reads and writes
+pub const DATA_VERSION: u64 = 5;
+
+/// migrations between data file versions, `MIGRATIONS[n]` upgrades version `n` to `n + 1`
+const MIGRATIONS: &[fn(&mut Value) -> Res<()>] = &[
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+];
+
+/// version 0 had no `version` field at all
+fn migrate_v0_to_v1(value: &mut Value) -> Res<()> {
+    let Some(object) = value.as_object_mut() else {
+        bail!("data file is not a JSON object");
+    };
+
+    object.entry("tracked_novels").or_insert_with(|| json!([]));
+    object.entry("recent_novels").or_insert_with(|| json!([]));
+    object.insert("version".to_owned(), json!(1));
+
+    Ok(())
+}
+
+/// version 2 added `status` and `tags` to every novel
+fn migrate_v1_to_v2(value: &mut Value) -> Res<()> {
+    for list in ["tracked_novels", "recent_novels"] {
+        let Some(novels) = value.get_mut(list).and_then(Value::as_array_mut) else {
+            bail!("`{list}` is not a list");
+        };
+
+        for novel in novels {
+            let Some(novel) = novel.as_object_mut() else {
+                bail!("`{list}` contains something that isn't a novel");
+            };
+
+            novel.entry("status").or_insert_with(|| json!("Unknown"));
+            novel.entry("tags").or_insert_with(|| json!([]));
+        }
+    }
+
+    value["version"] = json!(2);
+
+    Ok(())
+}
+
+/// version 3 added change timestamps to every novel and the list of untracked novels
+fn migrate_v2_to_v3(value: &mut Value) -> Res<()> {
+    for list in ["tracked_novels", "recent_novels"] {
+        let Some(novels) = value.get_mut(list).and_then(Value::as_array_mut) else {
+            bail!("`{list}` is not a list");
+        };
+
+        for novel in novels {
+            let Some(novel) = novel.as_object_mut() else {
+                bail!("`{list}` contains something that isn't a novel");
+            };
+
+            novel
+                .entry("stamps")
+                .or_insert_with(|| json!(Stamps::default()));
+        }
+    }
+
+    let Some(object) = value.as_object_mut() else {
+        bail!("data file is not a JSON object");
+    };
+
+    object.entry("untracked").or_insert_with(|| json!([]));
+    object.insert("version".to_owned(), json!(3));
+
+    Ok(())
+}
+
+/// version 4 identifies novels by an id made from their url instead of by name
+fn migrate_v3_to_v4(value: &mut Value) -> Res<()> {
+    for list in ["tracked_novels", "recent_novels", "untracked"] {
+        let Some(novels) = value.get_mut(list).and_then(Value::as_array_mut) else {
+            bail!("`{list}` is not a list");
+        };
+
+        for novel in novels {
+            let Some(novel) = novel.as_object_mut() else {
+                bail!("`{list}` contains something that isn't a novel");
+            };
+
+            let Some(url) = novel.get("url").and_then(Value::as_str) else {
+                bail!("`{list}` contains a novel without a url");
+            };
+
+            let id = novel_id(url);
+            novel.entry("id").or_insert_with(|| json!(id));
+        }
+    }
+
+    value["version"] = json!(4);
+
+    Ok(())
+}
+
+/// version 5 repairs ids that took a prefix like `/novel/` for the slug, or the `co` of `example.co.uk` for the site
+fn migrate_v4_to_v5(value: &mut Value) -> Res<()> {
+    for list in ["tracked_novels", "recent_novels", "untracked"] {
+        let Some(novels) = value.get_mut(list).and_then(Value::as_array_mut) else {
+            bail!("`{list}` is not a list");
+        };
+
+        for novel in novels {
+            let Some(novel) = novel.as_object_mut() else {
+                bail!("`{list}` contains something that isn't a novel");
+            };
+
+            let (Some(id), Some(url)) = (
+                novel.get("id").and_then(Value::as_str),
+                novel.get("url").and_then(Value::as_str),
+            ) else {
+                bail!("`{list}` contains a novel without an id or url");
+            };
+
+            let id = fixed_id(id, url);
+            novel.insert("id".to_owned(), json!(id));
+        }
+    }
+
+    value["version"] = json!(5);
+
+    Ok(())
+}
+
+/// how many backups of the data file to keep around, the first one of each hour
+const BACKUP_COUNT: usize = 10;
+
+fn timestamp() -> String {
+    Local::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+/// whether a backup is a corrupted data file that was moved aside
+pub fn is_corrupt_backup(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|x| x.to_string_lossy().starts_with("corrupt-"))
+}
+
+/// whether `data` parses as a data file from a newer version of lncli
+fn is_newer_version(data: &str) -> bool {
+    serde_json::from_str::<Value>(data).is_ok_and(|x| version_of(&x) > DATA_VERSION)
+}
+
+/// move a data file we can't read out of the way, so it can be recovered by hand
+fn quarantine(path: &Path) -> Res<PathBuf> {
+    let folder = Data::backup_folder();
+
+    std::fs::create_dir_all(&folder)?;
+
+    let backup = folder.join(format!("corrupt-{}.json", timestamp()));
+
+    std::fs::rename(path, &backup)?;
+
+    error!("moved corrupted data file to {}", backup.display());
+
+    Ok(backup)
+}
+
+/// get the version of a parsed data file, files from before versioning count as version 0
+fn version_of(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// run every migration needed to bring `value` up to [`DATA_VERSION`]
+fn migrate(mut value: Value) -> Res<Value> {
+    let version = version_of(&value);
+
+    if version > DATA_VERSION {
+        bail!(
+            "data file is version {}, but this lncli only understands up to version {}. Refusing to touch it",
+            version.yellow(),
+            DATA_VERSION.yellow()
+        );
+    }
+
+    for (from, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(usize::try_from(version)?)
+    {
+        migration(&mut value)
+            .map_err(|e| eyre!("failed to migrate data file from version {from}: {e}"))?;
+    }
+
+    Ok(value)
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Data {
+    version: u64,
+    #[serde(default)]
     tracked_novels: Vec<LN>,
+    #[serde(default)]
     recent_novels: VecDeque<LN>,
+    #[serde(default)]
+    untracked: Vec<Untracked>,
+    /// the tracked novels as they were when loaded, used to merge on save
+    #[serde(skip)]
+    base: Option<Vec<LN>>,
+    /// ids removed from the recent list since loading, so merging doesn't bring them back
+    #[serde(skip)]
+    removed_recent: Vec<String>,
 }
 
 impl Data {
@@ -26,65 +527,293 @@ impl Data {
         config_dir.join("lncli/")
     }
 
+    /// whether there is any stored data at all
+    pub fn exists() -> Res<bool> {
+        backend::exists()
+    }
+
     /// makes a new data
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
+            version: DATA_VERSION,
             tracked_novels: Vec::new(),
             recent_novels: VecDeque::new(),
+            untracked: Vec::new(),
+            base: None,
+            removed_recent: Vec::new(),
         }
     }
 
-    /// load data from file
+    /// load the stored data, migrating it from older versions if needed
     pub fn load() -> Res<Self> {
-        let path = Self::data_folder().join("data.json");
+        backend::load()
+    }
 
-        if !path.exists() {
-            bail!(
-                "data file does not exist: {}",
-                path.display().to_string().green()
-            );
+    /// parse the contents of a data file, migrating it from older versions if needed
+    fn parse(data: &str) -> Res<Self> {
+        Self::from_value(serde_json::from_str(data)?)
+    }
+
+    /// build the data out of a parsed document, migrating it from older versions if needed
+    fn from_value(data: Value) -> Res<Self> {
+        let data = migrate(data)?;
+        let mut data: Self = serde_json::from_value(data)?;
+
+        data.base = Some(data.tracked_novels.clone());
+
+        Ok(data)
+    }
+
+    fn backup_folder() -> PathBuf {
+        Self::data_folder().join("backups/")
+    }
+
+    /// snapshot the freshly saved data into the backup folder, dropping the oldest backups
+    fn rotate_backups(data: &Self, json: &str) -> Res<()> {
+        // an empty library is never worth a backup, and would push out the ones that matter
+        if data.library_size() == 0 {
+            return Ok(());
+        }
+
+        let folder = Self::backup_folder();
+
+        std::fs::create_dir_all(&folder)?;
+
+        let name = format!("data-{}.json", timestamp());
+        let newest = Self::backups()?.into_iter().find(|x| !is_corrupt_backup(x));
+
+        if let Some(newest) = newest {
+            if std::fs::read_to_string(&newest).is_ok_and(|x| x == json) {
+                return Ok(());
+            }
+
+            // keep the first backup of each hour, so turning a few chapters doesn't push out the older ones
+            if newest
+                .file_name()
+                .is_some_and(|x| x.to_string_lossy().get(..16) == name.get(..16))
+            {
+                return Ok(());
+            }
+        }
+
+        std::fs::write(folder.join(name), json)?;
+
+        let backups = Self::backups()?
+            .into_iter()
+            .filter(|x| !is_corrupt_backup(x))
+            .collect::<Vec<_>>();
+
+        // the biggest library stays even when it's the oldest, so shrinking it can't rotate it away
+        let largest = backups
+            .iter()
+            .rev()
+            .max_by_key(|x| Self::read_backup(x).map_or(0, |x| x.library_size()));
+
+        for old in backups
+            .iter()
+            .skip(BACKUP_COUNT)
+            .filter(|&x| Some(x) != largest)
+        {
+            std::fs::remove_file(old)?;
         }
 
-        let data = std::fs::read_to_string(&path)?;
+        Ok(())
+    }
 
-        Ok(serde_json::from_str(&data)?)
+    /// how many novels are tracked or recently read
+    fn library_size(&self) -> usize {
+        self.tracked_novels.len() + self.recent_novels.len()
     }
 
-    /// save the data to the data file
+    /// list the backups in the backup folder, newest first
+    pub fn backups() -> Res<Vec<PathBuf>> {
+        let folder = Self::backup_folder();
+
+        if !folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = std::fs::read_dir(folder)?
+            .filter_map(Result::ok)
+            .map(|x| x.path())
+            .filter(|x| x.extension().is_some_and(|x| x == "json"))
+            .collect::<Vec<_>>();
+
+        // sort by the timestamp after the `data-`/`corrupt-` prefix
+        backups.sort_by_key(|x| {
+            x.file_name()
+                .map(|x| x.to_string_lossy().split_once('-').map(|x| x.1.to_owned()))
+        });
+        backups.reverse();
+
+        Ok(backups)
+    }
+
+    /// read a backup without restoring it
+    pub fn read_backup(backup: &Path) -> Res<Self> {
+        let data = std::fs::read_to_string(backup)?;
+
+        Self::parse(&data)
+    }
+
+    /// replace the data file with the contents of a backup
+    pub fn restore(backup: &Path) -> Res<Self> {
+        let data = Self::read_backup(backup)?;
+
+        data.write(false)?;
+
+        Ok(data)
+    }
+
+    /// take an advisory lock on the data folder, released when the returned file is dropped
+    fn lock() -> Res<File> {
+        let folder = Self::data_folder();
+
+        std::fs::create_dir_all(&folder)?;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(folder.join("data.lock"))?;
+
+        file.lock()?;
+
+        Ok(file)
+    }
+
+    /// save the data to the data file, merging in changes another instance made since we loaded it
     pub fn save(&self) -> Res<()> {
-        let path = Self::data_folder().join("data.json");
+        self.write(true)
+    }
+
+    /// write the data under the lock
+    fn write(&self, merge: bool) -> Res<()> {
+        let _lock = Self::lock()?;
 
-        std::fs::create_dir_all(
-            path.parent()
-                .expect("Could not get parent directory of path."),
-        )?;
+        let current = backend::read_current()?;
 
         let mut data = self.clone();
+        data.stamp_changes();
 
+        if let Some(current) = current.filter(|_| merge) {
+            data = data.merged_with(current);
+        }
+
+        data.version = DATA_VERSION;
         data.prune();
 
-        let data = serde_json::to_string_pretty(&data)?;
+        let json = serde_json::to_string_pretty(&data)?;
+
+        backend::write(&data, &json)?;
 
-        std::fs::write(&path, data)?;
+        Self::rotate_backups(&data, &json)?;
 
         Ok(())
     }
 
+    /// timestamp everything that changed since we loaded, and remember what was untracked
+    fn stamp_changes(&mut self) {
+        let now = Utc::now();
+        let base = self.base.take().unwrap_or_default();
+
+        for novel in &mut self.tracked_novels {
+            novel.stamp_changes(base.iter().find(|x| x.id == novel.id), now);
+        }
+
+        for before in base {
+            if !self.tracked_novels.iter().any(|x| x.id == before.id) {
+                self.untracked.retain(|x| x.id != before.id);
+                self.untracked.push(Untracked {
+                    id: before.id,
+                    name: before.name,
+                    url: before.url,
+                    at: now,
+                });
+            }
+        }
+
+        self.base = Some(self.tracked_novels.clone());
+    }
+
+    /// merge our (already stamped) changes into what's on disk now
+    fn merged_with(&self, current: Self) -> Self {
+        let (tracked, untracked) = merge_tracked(
+            (&self.tracked_novels, &self.untracked),
+            (&current.tracked_novels, &current.untracked),
+        );
+
+        let mut recent = self.recent_novels.clone();
+        recent.extend(
+            current
+                .recent_novels
+                .into_iter()
+                .filter(|x| !self.removed_recent.contains(&x.id)),
+        );
+
+        Self {
+            version: DATA_VERSION,
+            tracked_novels: tracked,
+            recent_novels: recent,
+            untracked,
+            base: None,
+            removed_recent: Vec::new(),
+        }
+    }
+
+    /// replace the tracked list with the result of merging with another library.
+    /// the merged stamps are kept as they are, instead of counting as changes made now
+    pub fn set_merged(&mut self, tracked: Vec<LN>, untracked: Vec<Untracked>) {
+        self.tracked_novels = tracked;
+        self.untracked = untracked;
+        self.base = Some(self.tracked_novels.clone());
+    }
+
+    /// add an entry to the reading history
+    pub fn append_history(entry: &HistoryEntry) -> Res<()> {
+        backend::append_history(entry)
+    }
+
+    /// the whole reading history, oldest first
+    pub fn history() -> Res<Vec<HistoryEntry>> {
+        backend::history()
+    }
+
+    /// the reading history from `since` on, oldest first
+    pub fn history_since(since: DateTime<Utc>) -> Res<Vec<HistoryEntry>> {
+        backend::history_since(since)
+    }
+
+    /// the reading history of the novel at `url`, oldest first
+    pub fn history_of(url: &str) -> Res<Vec<HistoryEntry>> {
+        backend::history_of(url)
+    }
+
+    pub fn clear_history() -> Res<()> {
+        backend::clear_history()
+    }
+
+    /// get the novels that were untracked
+    pub fn untracked(&self) -> &[Untracked] {
+        &self.untracked
+    }
+
     fn prune(&mut self) {
-        self.recent_novels = self
-            .recent_novels
-            .clone()
+        let config = Config::load().unwrap_or_else(|e| {
+            error!("Failed to load the config, using the default recent list settings: {e}");
+            Config::default()
+        });
+
+        // newest first, entries from before `opened_at` was kept stay in the order they were saved in
+        let mut recent = Vec::from(std::mem::take(&mut self.recent_novels));
+        recent.sort_by_key(|x| Reverse(x.opened_at));
+
+        self.recent_novels = recent
             .into_iter()
             .fold(VecDeque::new(), |mut acc: VecDeque<LN>, x| {
-                if acc.iter().any(|v| v.name == x.name) {
-                    // get the item and compare the last chapter
-                    let item = acc
-                        .iter_mut()
-                        .find(|v| v.name == x.name)
-                        .expect("Could not find item in `acc` with matching name");
-
-                    if item.last_chapter < x.last_chapter {
-                        *item = x;
+                if let Some(item) = acc.iter_mut().find(|v| v.id == x.id) {
+                    if config.recent.chapter == RecentChapter::Furthest {
+                        item.last_chapter = item.last_chapter.max(x.last_chapter);
                     }
                 } else {
                     acc.push_back(x);
@@ -93,7 +822,7 @@ impl Data {
                 acc
             })
             .into_iter()
-            .take(10)
+            .take(config.recent.size)
             .collect::<VecDeque<_>>();
     }
 
@@ -103,17 +832,64 @@ impl Data {
     }
 
     /// get tracked novels but mutable
-    pub fn tracked_mut(&mut self) -> &mut Vec<LN> {
+    pub const fn tracked_mut(&mut self) -> &mut Vec<LN> {
         &mut self.tracked_novels
     }
 
     /// add a tracked novel, with a duplicate check
     pub fn add_tracked(&mut self, novel: LN) {
-        if self.tracked_novels.iter().any(|v| v.name == novel.name) {
+        if self.tracked_novels.iter().any(|v| v.id == novel.id) {
             return;
         }
 
-        self.tracked_novels.push(novel);
+        // when it was last opened only matters in the recent list
+        self.tracked_novels.push(LN {
+            opened_at: None,
+            ..novel
+        });
+    }
+
+    /// bring the stored urls up to date with the configured rewrite rules and the permanent redirects
+    /// seen while scraping, returning how many novels changed
+    pub fn update_urls(&mut self) -> usize {
+        let rules = rewrite::rules();
+        let redirects = rewrite::take_redirects();
+
+        let update = |novel: &mut LN| {
+            let mut url = rewrite::rewrite(rules, &novel.url);
+
+            // follow chains of redirects, but not forever if they loop
+            for _ in 0..redirects.len() {
+                let Some(redirect) = redirects
+                    .iter()
+                    .find(|x| novel_url(&x.from) == url && novel_url(&x.to) != url)
+                else {
+                    break;
+                };
+
+                url = novel_url(&redirect.to);
+            }
+
+            if url == novel.url {
+                return false;
+            }
+
+            info!(
+                "updating the url of {} to {}",
+                novel.name.green(),
+                url.yellow()
+            );
+            novel.url = url;
+
+            true
+        };
+
+        self.tracked_novels
+            .iter_mut()
+            .chain(self.recent_novels.iter_mut())
+            .map(update)
+            .filter(|&x| x)
+            .count()
     }
 
     /// get recent novels
@@ -121,8 +897,325 @@ impl Data {
         &self.recent_novels
     }
 
-    /// get recent novels but mutable
-    pub fn recent_mut(&mut self) -> &mut VecDeque<LN> {
-        &mut self.recent_novels
+    /// put a novel that was just opened at the top of the recent list
+    pub fn push_recent(&mut self, mut novel: LN) {
+        novel.opened_at = Some(Utc::now());
+
+        self.removed_recent.retain(|x| *x != novel.id);
+        self.recent_novels.push_front(novel);
+    }
+
+    /// remove every entry for a novel from the recent list
+    pub fn remove_recent(&mut self, novel: &LN) {
+        self.recent_novels.retain(|x| x.id != novel.id);
+        self.removed_recent.push(novel.id.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap_or_default()
+    }
+
+    fn novel(url: &str, last_chapter: usize, stamp: i64) -> LN {
+        LN {
+            stamps: Stamps {
+                tracked: at(stamp),
+                url: at(stamp),
+                last_chapter: at(stamp),
+                status: at(stamp),
+                tags: at(stamp),
+            },
+            ..LN::new("Martial Peak".to_owned(), url.to_owned(), last_chapter)
+        }
+    }
+
+    fn untracked(url: &str, stamp: i64) -> Untracked {
+        Untracked {
+            id: novel_id(url),
+            name: "Martial Peak".to_owned(),
+            url: url.to_owned(),
+            at: at(stamp),
+        }
+    }
+
+    const URL: &str = "https://freewebnovel.com/martial-peak.html";
+
+    #[test]
+    fn migrates_v0_to_v1() -> Res<()> {
+        let mut value = json!({ "tracked_novels": [{ "name": "Martial Peak", "url": URL, "last_chapter": 3 }] });
+
+        migrate_v0_to_v1(&mut value)?;
+
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["recent_novels"], json!([]));
+        assert_eq!(value["tracked_novels"][0]["last_chapter"], 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrates_v1_to_v2() -> Res<()> {
+        let mut value = json!({
+            "version": 1,
+            "tracked_novels": [{ "name": "Martial Peak", "url": URL, "last_chapter": 3 }],
+            "recent_novels": [{ "name": "Martial Peak", "url": URL, "last_chapter": 3, "tags": ["fav"] }],
+        });
+
+        migrate_v1_to_v2(&mut value)?;
+
+        assert_eq!(value["version"], 2);
+        assert_eq!(value["tracked_novels"][0]["status"], "Unknown");
+        assert_eq!(value["tracked_novels"][0]["tags"], json!([]));
+        assert_eq!(value["recent_novels"][0]["tags"], json!(["fav"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrates_v2_to_v3() -> Res<()> {
+        let mut value = json!({
+            "version": 2,
+            "tracked_novels": [{ "name": "Martial Peak", "url": URL, "last_chapter": 3, "status": "Ongoing", "tags": [] }],
+            "recent_novels": [],
+        });
+
+        migrate_v2_to_v3(&mut value)?;
+
+        assert_eq!(value["version"], 3);
+        assert_eq!(
+            value["tracked_novels"][0]["stamps"],
+            json!(Stamps::default())
+        );
+        assert_eq!(value["untracked"], json!([]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrates_v3_to_v4() -> Res<()> {
+        let mut value = json!({
+            "version": 3,
+            "tracked_novels": [{ "name": "Martial Peak", "url": URL, "last_chapter": 3 }],
+            "recent_novels": [{ "id": "kept/as-is", "name": "Martial Peak", "url": URL, "last_chapter": 3 }],
+            "untracked": [{ "name": "Martial Peak", "url": URL, "at": at(0) }],
+        });
+
+        migrate_v3_to_v4(&mut value)?;
+
+        assert_eq!(value["version"], 4);
+        assert_eq!(
+            value["tracked_novels"][0]["id"],
+            "freewebnovel/martial-peak"
+        );
+        assert_eq!(value["recent_novels"][0]["id"], "kept/as-is");
+        assert_eq!(value["untracked"][0]["id"], "freewebnovel/martial-peak");
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrates_v0_to_current() -> Res<()> {
+        let data = Data::parse(&json!({ "tracked_novels": [{ "name": "Martial Peak", "url": URL, "last_chapter": 3 }] }).to_string())?;
+
+        assert_eq!(data.version, DATA_VERSION);
+        assert_eq!(data.tracked()[0].id, "freewebnovel/martial-peak");
+        assert_eq!(data.tracked()[0].last_chapter, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let data = json!({ "version": DATA_VERSION + 1, "tracked_novels": [] }).to_string();
+
+        assert!(Data::parse(&data).is_err());
+        assert!(is_newer_version(&data));
+    }
+
+    #[test]
+    fn merge_keeps_the_newest_change_to_each_field() {
+        let mut ours = novel(URL, 1, 0);
+        ours.last_chapter = 20;
+        ours.stamps.last_chapter = at(20);
+
+        let mut theirs = novel(URL, 1, 0);
+        theirs.last_chapter = 10;
+        theirs.stamps.last_chapter = at(10);
+        theirs.tags = vec!["fav".to_owned()];
+        theirs.stamps.tags = at(30);
+
+        for (a, b) in [(&ours, &theirs), (&theirs, &ours)] {
+            let (tracked, untracked) = merge_tracked(
+                (std::slice::from_ref(a), &[]),
+                (std::slice::from_ref(b), &[]),
+            );
+
+            assert_eq!(tracked.len(), 1);
+            assert!(untracked.is_empty());
+            assert_eq!(tracked[0].last_chapter, 20);
+            assert_eq!(tracked[0].tags, ["fav"]);
+        }
+    }
+
+    #[test]
+    fn untracking_after_tracking_wins() {
+        let (tracked, untracked) =
+            merge_tracked((&[], &[untracked(URL, 20)]), (&[novel(URL, 5, 10)], &[]));
+
+        assert!(tracked.is_empty());
+        assert_eq!(untracked.len(), 1);
+    }
+
+    #[test]
+    fn tracking_again_after_untracking_wins() {
+        let (tracked, untracked) =
+            merge_tracked((&[], &[untracked(URL, 20)]), (&[novel(URL, 5, 30)], &[]));
+
+        assert_eq!(tracked.len(), 1);
+        assert!(untracked.is_empty());
+    }
+
+    #[test]
+    fn novel_id_ignores_tld_www_and_chapters() {
+        let id = novel_id(URL);
+
+        assert_eq!(id, "freewebnovel/martial-peak");
+        assert_eq!(
+            novel_id("https://www.freewebnovel.net/martial-peak.html"),
+            id
+        );
+        assert_eq!(
+            novel_id("https://freewebnovel.org/martial-peak/chapter-12.html"),
+            id
+        );
+        assert_eq!(
+            novel_id("http://WWW.FreeWebNovel.com/Martial-Peak.html"),
+            id
+        );
+        assert_ne!(novel_id("https://novelbin.com/martial-peak.html"), id);
+        assert_ne!(novel_id("https://freewebnovel.com/martial-god.html"), id);
+    }
+
+    #[test]
+    fn novel_id_skips_slug_prefixes() {
+        let id = "example/martial-peak";
+
+        assert_eq!(novel_id("https://example.com/novel/martial-peak.html"), id);
+        assert_eq!(
+            novel_id("https://example.com/book/martial-peak/chapter-3"),
+            id
+        );
+        assert_eq!(novel_id("https://example.com/b/martial-peak/"), id);
+        assert_eq!(
+            novel_id("https://example.com/series/martial-peak/chapters/12"),
+            id
+        );
+        // nothing but a prefix falls back to the last segment
+        assert_eq!(novel_id("https://example.com/novel/"), "example/novel");
+    }
+
+    #[test]
+    fn novel_id_handles_multi_part_tlds() {
+        assert_eq!(
+            novel_id("https://example.co.uk/martial-peak.html"),
+            "example/martial-peak"
+        );
+        assert_eq!(
+            novel_id("https://www.example.com.au/martial-peak.html"),
+            "example/martial-peak"
+        );
+        assert_eq!(
+            novel_id("https://m.example.co.uk/martial-peak.html"),
+            "m-example/martial-peak"
+        );
+        assert_eq!(
+            novel_id("https://co.uk/martial-peak.html"),
+            "co/martial-peak"
+        );
+    }
+
+    #[test]
+    fn migrates_v4_to_v5() -> Res<()> {
+        let prefixed = "https://example.co.uk/novel/martial-peak.html";
+        let mut value = json!({
+            "version": 4,
+            "tracked_novels": [{ "id": "example-co/novel", "url": prefixed }],
+            "recent_novels": [{ "id": "freewebnovel/martial-peak", "url": URL }],
+            // made from the url before it was rewritten, so left alone
+            "untracked": [{ "id": "oldsite/martial-peak", "url": prefixed }],
+        });
+
+        migrate_v4_to_v5(&mut value)?;
+
+        assert_eq!(value["version"], 5);
+        assert_eq!(value["tracked_novels"][0]["id"], "example/martial-peak");
+        assert_eq!(value["recent_novels"][0]["id"], "freewebnovel/martial-peak");
+        assert_eq!(value["untracked"][0]["id"], "oldsite/martial-peak");
+
+        Ok(())
+    }
+
+    #[test]
+    fn fix_ids_fills_in_and_repairs_ids() {
+        let mut novels = [
+            novel(URL, 1, 0),
+            novel("https://example.com/book/x.html", 1, 0),
+        ];
+        novels[0].id = String::new();
+        novels[1].id = "example/book".to_owned();
+
+        fix_ids(&mut novels, &mut []);
+
+        assert_eq!(novels[0].id, "freewebnovel/martial-peak");
+        assert_eq!(novels[1].id, "example/x");
+    }
+
+    #[test]
+    #[cfg(not(feature = "sqlite"))]
+    fn restores_a_backup_after_the_data_file_is_quarantined() -> Res<()> {
+        let home = std::env::temp_dir().join(format!("lncli-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        std::env::set_var("XDG_CONFIG_HOME", &home);
+
+        let mut data = Data::new();
+        data.add_tracked(LN::new("Martial Peak".to_owned(), URL.to_owned(), 3));
+        data.save()?;
+
+        // a later save in the same hour keeps the first backup
+        let mut data = Data::load()?;
+        data.add_tracked(LN::new(
+            "Martial God".to_owned(),
+            "https://freewebnovel.com/martial-god.html".to_owned(),
+            1,
+        ));
+        data.save()?;
+
+        std::fs::write(Data::data_folder().join("data.json"), "{ not json")?;
+
+        assert!(Data::load().is_err());
+        assert!(Data::backups()?.iter().any(|x| is_corrupt_backup(x)));
+
+        // starting over with an empty library doesn't touch the backups
+        Data::new().save()?;
+
+        let backups = Data::backups()?
+            .into_iter()
+            .filter(|x| !is_corrupt_backup(x))
+            .collect::<Vec<_>>();
+        assert_eq!(backups.len(), 1);
+
+        Data::restore(&backups[0])?;
+
+        let data = Data::load()?;
+        assert_eq!(data.tracked().len(), 1);
+        assert_eq!(data.tracked()[0].url, URL);
+
+        std::fs::remove_dir_all(&home)?;
+
+        Ok(())
     }
 }
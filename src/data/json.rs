@@ -0,0 +1,147 @@
+//! the default storage, a single pretty-printed `data.json`
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::bail;
+use log::error;
+use owo_colors::OwoColorize;
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::{is_newer_version, quarantine, Data};
+use crate::{history::HistoryEntry, Res};
+
+fn data_file() -> PathBuf {
+    Data::data_folder().join("data.json")
+}
+
+pub fn exists() -> Res<bool> {
+    Ok(data_file().try_exists()?)
+}
+
+/// load the data file, moving it aside if it turns out to be corrupted
+pub fn load() -> Res<Data> {
+    let path = data_file();
+
+    if !path.exists() {
+        bail!(
+            "data file does not exist: {}",
+            path.display().to_string().green()
+        );
+    }
+
+    let data = std::fs::read_to_string(&path)?;
+
+    match Data::parse(&data) {
+        Ok(data) => Ok(data),
+        Err(e) if is_newer_version(&data) => Err(e),
+        Err(e) => {
+            let backup = quarantine(&path)?;
+
+            bail!(
+                "data file was corrupted ({e}), it has been moved to {}",
+                backup.display().to_string().green()
+            );
+        }
+    }
+}
+
+/// read what's currently on disk for merging, refusing to continue if it's there but unreadable
+pub fn read_current() -> Res<Option<Data>> {
+    let path = data_file();
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let data = std::fs::read_to_string(&path)?;
+
+    if is_newer_version(&data) {
+        bail!(
+            "refusing to overwrite data file written by a newer lncli: {}",
+            path.display().to_string().green()
+        );
+    }
+
+    let Ok(data) = Data::parse(&data) else {
+        bail!(
+            "refusing to overwrite unreadable data file: {}",
+            path.display().to_string().green()
+        );
+    };
+
+    Ok(Some(data))
+}
+
+/// replace the data file through a temporary file, so a crash never leaves it half-written
+pub fn write(_data: &Data, json: &str) -> Res<()> {
+    let path = data_file();
+
+    let temp = path.with_extension("json.tmp");
+
+    std::fs::write(&temp, json)?;
+    std::fs::rename(&temp, &path)?;
+
+    Ok(())
+}
+
+fn history_file() -> PathBuf {
+    Data::data_folder().join("history.jsonl")
+}
+
+/// history is kept as one JSON entry per line, so recording a chapter is a single append
+pub fn append_history(entry: &HistoryEntry) -> Res<()> {
+    std::fs::create_dir_all(Data::data_folder())?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_file())?;
+
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+
+    Ok(())
+}
+
+pub fn history() -> Res<Vec<HistoryEntry>> {
+    let path = history_file();
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let history = std::fs::read_to_string(path)?;
+
+    Ok(history
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .filter_map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| error!("skipping unreadable history entry: {e}"))
+                .ok()
+        })
+        .collect())
+}
+
+pub fn history_since(since: DateTime<Utc>) -> Res<Vec<HistoryEntry>> {
+    let mut history = history()?;
+    history.retain(|x| x.opened_at >= since);
+
+    Ok(history)
+}
+
+pub fn history_of(url: &str) -> Res<Vec<HistoryEntry>> {
+    let mut history = history()?;
+    history.retain(|x| x.url == url);
+
+    Ok(history)
+}
+
+pub fn clear_history() -> Res<()> {
+    let path = history_file();
+
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
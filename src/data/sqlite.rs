@@ -0,0 +1,311 @@
+//! storage in a sqlite database, enabled with the `sqlite` feature
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::bail;
+use log::{error, info};
+use owo_colors::OwoColorize;
+use parking_lot::{const_mutex, Mutex};
+use rusqlite::{params, Connection, OptionalExtension, Params};
+use serde_json::{json, Value};
+
+use super::{is_newer_version, quarantine, timestamp, Data};
+use crate::{history::HistoryEntry, Res};
+
+/// the database schema, `SCHEMA[n]` upgrades `user_version` `n` to `n + 1`
+const SCHEMA: &[&str] = &[
+    "
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE TABLE tracked (
+        position INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        url TEXT NOT NULL,
+        novel TEXT NOT NULL
+    );
+    CREATE INDEX tracked_url ON tracked (url);
+
+    CREATE TABLE recent (
+        position INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        url TEXT NOT NULL,
+        novel TEXT NOT NULL
+    );
+",
+    "
+    CREATE TABLE history (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        url TEXT NOT NULL,
+        chapter INTEGER NOT NULL,
+        opened_at TEXT NOT NULL,
+        seconds INTEGER NOT NULL,
+        entry TEXT NOT NULL
+    );
+    CREATE INDEX history_opened_at ON history (opened_at);
+    CREATE INDEX history_url ON history (url, chapter);
+",
+];
+
+/// the database, opened the first time it's needed and kept open after
+static CONNECTION: Mutex<Option<Connection>> = const_mutex(None);
+
+/// run `f` on the database, opening it first if it isn't yet
+fn with_connection<T>(f: impl FnOnce(&Connection) -> Res<T>) -> Res<T> {
+    let mut conn = CONNECTION.lock();
+
+    if conn.is_none() {
+        *conn = Some(open()?);
+    }
+
+    let result = conn.as_ref().map(f);
+    drop(conn);
+
+    result.unwrap_or_else(|| bail!("library database is not open"))
+}
+
+/// open the database, creating or upgrading its schema and importing `data.json` the first time
+fn open() -> Res<Connection> {
+    let folder = Data::data_folder();
+
+    std::fs::create_dir_all(&folder)?;
+
+    let conn = Connection::open(folder.join("library.db"))?;
+
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    for (i, sql) in SCHEMA.iter().enumerate().skip(version) {
+        conn.execute_batch(sql)?;
+        conn.pragma_update(None, "user_version", i + 1)?;
+    }
+
+    // a data file that can't be imported mustn't keep the database from opening
+    if let Err(e) = import_json(&conn) {
+        error!("Failed to import data.json: {e}");
+    }
+
+    Ok(conn)
+}
+
+/// one-time import of an existing `data.json` into a database that's still empty,
+/// moving the file aside if it can't be read
+fn import_json(conn: &Connection) -> Res<()> {
+    let imported: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'imported_json'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if imported.is_some() || read_document(conn)?.is_some() {
+        return Ok(());
+    }
+
+    let path = Data::data_folder().join("data.json");
+
+    if path.exists() {
+        let json = std::fs::read_to_string(&path)?;
+
+        match Data::parse(&json) {
+            Ok(data) => {
+                write_data(conn, &data)?;
+
+                info!(
+                    "imported {} tracked novels from {}",
+                    data.tracked().len().yellow(),
+                    path.display().to_string().green()
+                );
+            }
+            Err(e) if is_newer_version(&json) => {
+                error!("Skipping data.json written by a newer lncli: {e}");
+            }
+            Err(e) => {
+                error!("Skipping unreadable data.json: {e}");
+                quarantine(&path)?;
+            }
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES ('imported_json', ?1)",
+        [timestamp()],
+    )?;
+
+    Ok(())
+}
+
+/// rebuild the data document out of the database, `None` if nothing has been stored yet
+fn read_document(conn: &Connection) -> Res<Option<Value>> {
+    let version: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get(0)
+        })
+        .optional()?;
+
+    let Some(version) = version else {
+        return Ok(None);
+    };
+
+    let novels = |table: &str| -> Res<Vec<Value>> {
+        let mut statement =
+            conn.prepare(&format!("SELECT novel FROM {table} ORDER BY position"))?;
+
+        let novels = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|novel| Ok(serde_json::from_str(&novel?)?))
+            .collect::<Res<Vec<Value>>>()?;
+
+        Ok(novels)
+    };
+
+    let extra: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = 'extra'", [], |row| {
+            row.get(0)
+        })
+        .optional()?;
+
+    let mut document = match extra {
+        Some(extra) => serde_json::from_str(&extra)?,
+        None => json!({}),
+    };
+
+    document["version"] = json!(version.parse::<u64>()?);
+    document["tracked_novels"] = json!(novels("tracked")?);
+    document["recent_novels"] = json!(novels("recent")?);
+
+    Ok(Some(document))
+}
+
+fn write_data(conn: &Connection, data: &Data) -> Res<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    tx.execute("DELETE FROM tracked", [])?;
+    tx.execute("DELETE FROM recent", [])?;
+
+    for (position, novel) in data.tracked().iter().enumerate() {
+        tx.execute(
+            "INSERT INTO tracked (position, name, url, novel) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                position,
+                novel.name,
+                novel.url,
+                serde_json::to_string(novel)?
+            ],
+        )?;
+    }
+
+    for (position, novel) in data.recent().iter().enumerate() {
+        tx.execute(
+            "INSERT INTO recent (position, name, url, novel) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                position,
+                novel.name,
+                novel.url,
+                serde_json::to_string(novel)?
+            ],
+        )?;
+    }
+
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('version', ?1)",
+        [data.version.to_string()],
+    )?;
+
+    // everything that doesn't have a table of its own
+    let mut extra = serde_json::to_value(data)?;
+
+    if let Some(extra) = extra.as_object_mut() {
+        extra.remove("version");
+        extra.remove("tracked_novels");
+        extra.remove("recent_novels");
+    }
+
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('extra', ?1)",
+        [extra.to_string()],
+    )?;
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn exists() -> Res<bool> {
+    with_connection(|conn| Ok(read_document(conn)?.is_some()))
+}
+
+pub fn load() -> Res<Data> {
+    let Some(document) = with_connection(read_document)? else {
+        bail!("library database is empty");
+    };
+
+    Data::from_value(document)
+}
+
+pub fn read_current() -> Res<Option<Data>> {
+    with_connection(read_document)?
+        .map(Data::from_value)
+        .transpose()
+}
+
+pub fn write(data: &Data, _json: &str) -> Res<()> {
+    with_connection(|conn| write_data(conn, data))
+}
+
+pub fn append_history(entry: &HistoryEntry) -> Res<()> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO history (name, url, chapter, opened_at, seconds, entry) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.name,
+                entry.url,
+                entry.chapter,
+                entry.opened_at.to_rfc3339(),
+                entry.seconds,
+                serde_json::to_string(entry)?
+            ],
+        )?;
+
+        Ok(())
+    })
+}
+
+/// the history entries picked out by `filter`, oldest first
+fn query_history(filter: &str, params: impl Params) -> Res<Vec<HistoryEntry>> {
+    with_connection(|conn| {
+        let mut statement = conn.prepare(&format!(
+            "SELECT entry FROM history {filter} ORDER BY opened_at, id"
+        ))?;
+
+        let history = statement
+            .query_map(params, |row| row.get::<_, String>(0))?
+            .map(|entry| Ok(serde_json::from_str(&entry?)?))
+            .collect::<Res<Vec<_>>>()?;
+
+        Ok(history)
+    })
+}
+
+pub fn history() -> Res<Vec<HistoryEntry>> {
+    query_history("", [])
+}
+
+/// `opened_at` is always stored in UTC, so the text sorts the same as the time
+pub fn history_since(since: DateTime<Utc>) -> Res<Vec<HistoryEntry>> {
+    query_history("WHERE opened_at >= ?1", [since.to_rfc3339()])
+}
+
+pub fn history_of(url: &str) -> Res<Vec<HistoryEntry>> {
+    query_history("WHERE url = ?1", [url])
+}
+
+pub fn clear_history() -> Res<()> {
+    with_connection(|conn| {
+        conn.execute("DELETE FROM history", [])?;
+
+        Ok(())
+    })
+}
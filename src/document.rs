@@ -0,0 +1,205 @@
+//! a chapter's text with the bit of formatting worth keeping from its html
+
+use serde::{Deserialize, Serialize};
+use visdom::types::{BoxDynNode, Elements, IEnumTyped};
+
+/// what a scene break is shown as in the plain text
+pub const SCENE_BREAK: &str = "* * *";
+
+/// a run of text, in italics for emphasis and in bold when strong
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Span {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub emphasis: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub strong: bool,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_false(x: &bool) -> bool {
+    !*x
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Block {
+    Paragraph(Vec<Span>),
+    Heading(Vec<Span>),
+    Quote(Vec<Span>),
+    SceneBreak,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+}
+
+impl Document {
+    /// the paragraphs, headings, quotes and scene breaks directly inside `top`
+    pub fn from_html(top: &Elements) -> Self {
+        let mut blocks = Vec::new();
+
+        for el in top.children("p, h1, h2, h3, h4, h5, h6, blockquote, hr") {
+            let tag = el.tag_name().to_lowercase();
+
+            if tag == "hr" {
+                blocks.push(Block::SceneBreak);
+                continue;
+            }
+
+            let mut spans = Vec::new();
+
+            for node in el.child_nodes() {
+                walk(node, false, false, &mut spans);
+            }
+
+            let spans = tidy(spans);
+
+            if spans.is_empty() {
+                continue;
+            }
+
+            blocks.push(match tag.as_str() {
+                "blockquote" => Block::Quote(spans),
+                "p" if is_scene_break(&plain(&spans)) => Block::SceneBreak,
+                "p" => Block::Paragraph(spans),
+                _ => Block::Heading(spans),
+            });
+        }
+
+        Self { blocks }
+    }
+
+    /// plain paragraphs split on blank lines, for chapters saved before there was any formatting
+    pub fn from_text(text: &str) -> Self {
+        let blocks = text
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                if is_scene_break(x) {
+                    Block::SceneBreak
+                } else {
+                    Block::Paragraph(vec![Span {
+                        text: x.to_owned(),
+                        ..Span::default()
+                    }])
+                }
+            })
+            .collect();
+
+        Self { blocks }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// the text without its formatting, a blank line between blocks
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+
+        for block in &self.blocks {
+            match block {
+                Block::Paragraph(spans) | Block::Heading(spans) | Block::Quote(spans) => {
+                    text.push_str(&plain(spans));
+                }
+                Block::SceneBreak => text.push_str(SCENE_BREAK),
+            }
+
+            text.push_str("\n\n");
+        }
+
+        text
+    }
+}
+
+/// collect the text under `node`, noting whether it's emphasised or strong
+fn walk(node: BoxDynNode, emphasis: bool, strong: bool, spans: &mut Vec<Span>) {
+    match node.typed() {
+        IEnumTyped::Text(text) => spans.push(Span {
+            text: text.text_content(),
+            emphasis,
+            strong,
+        }),
+        IEnumTyped::Element(el) => {
+            let tag = el.tag_name().to_lowercase();
+
+            if tag == "br" {
+                spans.push(Span {
+                    text: " ".to_owned(),
+                    ..Span::default()
+                });
+                return;
+            }
+
+            let emphasis = emphasis || matches!(tag.as_str(), "em" | "i");
+            let strong = strong || matches!(tag.as_str(), "strong" | "b");
+
+            for child in el.child_nodes() {
+                walk(child, emphasis, strong, spans);
+            }
+        }
+        IEnumTyped::UncareNode(_) => {}
+    }
+}
+
+/// collapse runs of whitespace like a browser would, trim the ends and drop empty spans
+fn tidy(spans: Vec<Span>) -> Vec<Span> {
+    let mut tidied: Vec<Span> = Vec::new();
+    // start as if after a space, so leading whitespace goes
+    let mut after_space = true;
+
+    for span in spans {
+        let mut text = String::new();
+
+        for c in span.text.chars() {
+            if c.is_whitespace() {
+                if !after_space {
+                    text.push(' ');
+                }
+                after_space = true;
+            } else {
+                text.push(c);
+                after_space = false;
+            }
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        match tidied.last_mut() {
+            Some(last) if last.emphasis == span.emphasis && last.strong == span.strong => {
+                last.text.push_str(&text);
+            }
+            _ => tidied.push(Span { text, ..span }),
+        }
+    }
+
+    if let Some(last) = tidied.last_mut() {
+        last.text.truncate(last.text.trim_end().len());
+
+        if last.text.is_empty() {
+            tidied.pop();
+        }
+    }
+
+    tidied
+}
+
+fn plain(spans: &[Span]) -> String {
+    spans.iter().map(|x| x.text.as_str()).collect()
+}
+
+/// a line of nothing but a few symbols, like `***` or `~ ~ ~`, standing between two scenes,
+/// and not something like `...` that someone says
+fn is_scene_break(text: &str) -> bool {
+    let marks = text
+        .chars()
+        .filter(|x| !x.is_whitespace())
+        .collect::<Vec<_>>();
+
+    (3..=20).contains(&marks.len()) && marks.iter().all(|x| "*~-=_#+◇◆○●•☆★⁂".contains(*x))
+}
@@ -0,0 +1,77 @@
+//! the reading history, one entry for every time a chapter was opened
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::Res;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// the novel's name
+    pub name: String,
+    /// the novel's main page
+    pub url: String,
+    pub chapter: usize,
+    pub chapter_title: String,
+    pub opened_at: DateTime<Utc>,
+    /// how long the chapter was open for
+    pub seconds: u64,
+    /// the chapter's length, entries from before this was recorded count as 0
+    #[serde(default)]
+    pub words: usize,
+    /// the novel's chapter count at the time
+    #[serde(default)]
+    pub max_chapters: usize,
+}
+
+impl HistoryEntry {
+    pub fn chapter_url(&self) -> String {
+        self.url
+            .replace(".html", &format!("/chapter-{}.html", self.chapter))
+    }
+}
+
+/// format a duration in seconds the way the history view shows it
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// write the history to a csv file
+pub fn export(entries: &[HistoryEntry], path: &Path) -> Res<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    writer.write_record([
+        "opened_at",
+        "name",
+        "url",
+        "chapter",
+        "chapter_title",
+        "seconds",
+        "words",
+    ])?;
+
+    for entry in entries {
+        writer.write_record([
+            entry.opened_at.with_timezone(&Local).to_rfc3339(),
+            entry.name.clone(),
+            entry.url.clone(),
+            entry.chapter.to_string(),
+            entry.chapter_title.clone(),
+            entry.seconds.to_string(),
+            entry.words.to_string(),
+        ])?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
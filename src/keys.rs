@@ -0,0 +1,410 @@
+//! keybindings, the defaults overridden by the `[keys]` table in the config
+
+use color_eyre::eyre::{bail, eyre};
+use cursive::{
+    event::{Event, Key},
+    theme::{BaseColor, Color},
+    utils::markup::StyledString,
+    views::OnEventView,
+    Cursive, View,
+};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::{config::Config, Res};
+
+/// everything that can be bound to a key, named in the config like in [`DEFAULTS`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Action {
+    Quit,
+    DebugConsole,
+    // reader
+    ChapterSelect,
+    NextChapter,
+    PreviousChapter,
+    Home,
+    OpenInBrowser,
+    WiderText,
+    NarrowerText,
+    NextPage,
+    PreviousPage,
+    ReadingMode,
+    PauseScroll,
+    ScrollFaster,
+    ScrollSlower,
+    Find,
+    NextMatch,
+    PreviousMatch,
+    // home
+    Reader,
+    Search,
+    UpdateCheck,
+    Browse,
+    Backups,
+    History,
+    Stats,
+    SearchChapters,
+    Themes,
+    Track,
+    Tags,
+    Migrate,
+    RemoveRecent,
+    Details,
+    // search results
+    MoreResults,
+    // novel details
+    ReadFromStart,
+    Continue,
+    // reading history
+    ClearHistory,
+    ExportHistory,
+}
+
+/// every action with its name in the config and its default key
+const DEFAULTS: &[(Action, &str, &str)] = &[
+    (Action::Quit, "quit", "q"),
+    (Action::DebugConsole, "debug_console", "D"),
+    (Action::ChapterSelect, "chapter_select", "c"),
+    (Action::NextChapter, "next_chapter", "right"),
+    (Action::PreviousChapter, "previous_chapter", "left"),
+    (Action::Home, "home", "h"),
+    (Action::OpenInBrowser, "open_in_browser", "O"),
+    (Action::WiderText, "wider_text", "+"),
+    (Action::NarrowerText, "narrower_text", "-"),
+    (Action::NextPage, "next_page", "space"),
+    (Action::PreviousPage, "previous_page", "backspace"),
+    (Action::ReadingMode, "reading_mode", "P"),
+    (Action::PauseScroll, "pause_scroll", "space"),
+    (Action::ScrollFaster, "scroll_faster", "]"),
+    (Action::ScrollSlower, "scroll_slower", "["),
+    (Action::Find, "find", "/"),
+    (Action::NextMatch, "next_match", "n"),
+    (Action::PreviousMatch, "previous_match", "N"),
+    (Action::Reader, "reader", "r"),
+    (Action::Search, "search", "s"),
+    (Action::UpdateCheck, "update_check", "u"),
+    (Action::Browse, "browse", "b"),
+    (Action::Backups, "backups", "B"),
+    (Action::History, "history", "H"),
+    (Action::Stats, "stats", "S"),
+    (Action::SearchChapters, "search_chapters", "F"),
+    (Action::Themes, "themes", "C"),
+    (Action::Track, "track", "t"),
+    (Action::Tags, "tags", "T"),
+    (Action::Migrate, "migrate", "M"),
+    (Action::RemoveRecent, "remove_recent", "d"),
+    (Action::Details, "details", "i"),
+    (Action::MoreResults, "more_results", "m"),
+    (Action::ReadFromStart, "read_from_start", "r"),
+    (Action::Continue, "continue", "c"),
+    (Action::ClearHistory, "clear_history", "c"),
+    (Action::ExportHistory, "export_history", "e"),
+];
+
+impl TryFrom<String> for Action {
+    type Error = color_eyre::Report;
+
+    fn try_from(name: String) -> Res<Self> {
+        DEFAULTS
+            .iter()
+            .find(|x| x.1 == name)
+            .map(|x| x.0)
+            .ok_or_else(|| eyre!("unknown action `{name}`"))
+    }
+}
+
+impl From<Action> for String {
+    fn from(action: Action) -> Self {
+        DEFAULTS
+            .iter()
+            .find(|x| x.0 == action)
+            .map_or_else(Self::new, |x| x.1.to_owned())
+    }
+}
+
+/// a set of keybindings to start from, before the ones in `[keys]`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Keymap {
+    #[default]
+    Default,
+    /// `j`/`k` to move, `g`/`G` for the top and bottom, `ctrl-d`/`ctrl-u` for half a page,
+    /// `h`/`l` or `p`/`n` to switch chapters and `/` to search
+    Vim,
+}
+
+/// the bindings the vim keymap changes, to keep its movement keys free
+const VIM_BINDINGS: &[(Action, &str)] = &[(Action::Home, "H"), (Action::Search, "/")];
+
+/// keys the vim keymap turns into others before the views see them
+const VIM_TRANSLATIONS: &[(&str, &str)] = &[
+    ("j", "down"),
+    ("k", "up"),
+    ("g", "home"),
+    ("G", "end"),
+    ("h", "left"),
+    ("l", "right"),
+];
+
+/// a key, written like `q`, `ctrl-r`, `left` or `f5`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Binding {
+    name: String,
+    event: Event,
+}
+
+impl TryFrom<String> for Binding {
+    type Error = color_eyre::Report;
+
+    fn try_from(name: String) -> Res<Self> {
+        let lower = name.to_lowercase();
+
+        let event = match lower.as_str() {
+            _ if name.chars().count() == 1 => Event::Char(name.chars().next().unwrap_or_default()),
+            "space" => Event::Char(' '),
+            "left" => Event::Key(Key::Left),
+            "right" => Event::Key(Key::Right),
+            "up" => Event::Key(Key::Up),
+            "down" => Event::Key(Key::Down),
+            "pageup" => Event::Key(Key::PageUp),
+            "pagedown" => Event::Key(Key::PageDown),
+            "home" => Event::Key(Key::Home),
+            "end" => Event::Key(Key::End),
+            "tab" => Event::Key(Key::Tab),
+            "backspace" => Event::Key(Key::Backspace),
+            "del" | "delete" => Event::Key(Key::Del),
+            "ins" | "insert" => Event::Key(Key::Ins),
+            key => {
+                if let Some(c) = key.strip_prefix("ctrl-").filter(|x| x.chars().count() == 1) {
+                    Event::CtrlChar(c.chars().next().unwrap_or_default())
+                } else if let Some(n) = key.strip_prefix('f').and_then(|x| x.parse::<u8>().ok()) {
+                    Event::Key(function_key(n).ok_or_else(|| eyre!("there is no F{n} key"))?)
+                } else {
+                    bail!("unknown key `{name}`, expected a character or a key like `ctrl-r`, `left` or `f5`");
+                }
+            }
+        };
+
+        Ok(Self { name, event })
+    }
+}
+
+impl From<Binding> for String {
+    fn from(binding: Binding) -> Self {
+        binding.name
+    }
+}
+
+const fn function_key(n: u8) -> Option<Key> {
+    Some(match n {
+        1 => Key::F1,
+        2 => Key::F2,
+        3 => Key::F3,
+        4 => Key::F4,
+        5 => Key::F5,
+        6 => Key::F6,
+        7 => Key::F7,
+        8 => Key::F8,
+        9 => Key::F9,
+        10 => Key::F10,
+        11 => Key::F11,
+        12 => Key::F12,
+        _ => return None,
+    })
+}
+
+struct Keys {
+    keymap: Keymap,
+    bindings: BTreeMap<Action, Binding>,
+}
+
+static KEYS: OnceLock<Keys> = OnceLock::new();
+
+/// the defaults, then the keymap, then the configured keys on top, read once
+fn keys() -> &'static Keys {
+    KEYS.get_or_init(|| {
+        let config = Config::load().unwrap_or_else(|e| {
+            error!("Failed to load the config, using the default keys: {e}");
+            Config::default()
+        });
+
+        resolve(config)
+    })
+}
+
+/// the defaults, then the keymap, then the keys from `config` on top
+fn resolve(config: Config) -> Keys {
+    let keymap = match config.keymap {
+        Keymap::Default => &[][..],
+        Keymap::Vim => VIM_BINDINGS,
+    };
+
+    let mut bindings = DEFAULTS
+        .iter()
+        .map(|&(action, _, key)| (action, key))
+        .chain(keymap.iter().copied())
+        .filter_map(|(action, key)| Some((action, Binding::try_from(key.to_owned()).ok()?)))
+        .collect::<BTreeMap<_, _>>();
+
+    bindings.extend(config.keys);
+
+    Keys {
+        keymap: config.keymap,
+        bindings,
+    }
+}
+
+fn parse(key: &str) -> Option<Event> {
+    Binding::try_from(key.to_owned()).ok().map(|x| x.event)
+}
+
+/// pairs of keys where the first stands in for the second
+pub fn translations() -> Vec<(Event, Event)> {
+    keymap_translations(keys().keymap)
+}
+
+fn keymap_translations(keymap: Keymap) -> Vec<(Event, Event)> {
+    match keymap {
+        Keymap::Default => Vec::new(),
+        Keymap::Vim => VIM_TRANSLATIONS
+            .iter()
+            .filter_map(|(from, to)| Some((parse(from)?, parse(to)?)))
+            .collect(),
+    }
+}
+
+/// the key that moves half a page down, if the keymap has one
+pub fn half_page_down() -> Option<Event> {
+    (keys().keymap == Keymap::Vim).then_some(Event::CtrlChar('d'))
+}
+
+/// the key that moves half a page up, if the keymap has one
+pub fn half_page_up() -> Option<Event> {
+    (keys().keymap == Keymap::Vim).then_some(Event::CtrlChar('u'))
+}
+
+fn binding(action: Action) -> Option<&'static Binding> {
+    keys().bindings.get(&action)
+}
+
+/// the event that triggers `action`, if it's bound to anything
+pub fn event(action: Action) -> Option<Event> {
+    binding(action).map(|x| x.event.clone())
+}
+
+/// callbacks on actions rather than keys, left out for actions that aren't bound to anything
+pub trait OnAction {
+    fn on_action<F: Fn(&mut Cursive) + 'static>(self, action: Action, cb: F) -> Self;
+
+    fn set_on_action<F: Fn(&mut Cursive) + 'static>(&mut self, action: Action, cb: F);
+}
+
+impl<T: View> OnAction for OnEventView<T> {
+    fn on_action<F: Fn(&mut Cursive) + 'static>(mut self, action: Action, cb: F) -> Self {
+        self.set_on_action(action, cb);
+        self
+    }
+
+    fn set_on_action<F: Fn(&mut Cursive) + 'static>(&mut self, action: Action, cb: F) {
+        if let Some(event) = event(action) {
+            self.set_on_event(event, cb);
+        }
+    }
+}
+
+/// the name of the key bound to `action`
+pub fn name(action: Action) -> &'static str {
+    binding(action).map_or("", |x| x.name.as_str())
+}
+
+/// append a hint for `action`, highlighting the key in `description` when it starts with it, e.g. `q` + `uit`
+pub fn hint(s: &mut StyledString, action: Action, description: &str) {
+    let key = name(action);
+
+    s.append_styled(key, Color::Dark(BaseColor::Yellow));
+
+    match description
+        .strip_prefix(key)
+        .filter(|_| key.chars().count() == 1)
+    {
+        Some(rest) => s.append_plain(rest),
+        None => s.append_plain(format!(" {description}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(keys: &Keys, action: Action) -> &str {
+        keys.bindings.get(&action).map_or("", |x| x.name.as_str())
+    }
+
+    #[test]
+    fn every_default_key_parses_and_every_action_has_a_name() {
+        let keys = resolve(Config::default());
+
+        for &(action, name, default) in DEFAULTS {
+            assert_eq!(key(&keys, action), default);
+            assert_eq!(String::from(action), name);
+            assert_eq!(Action::try_from(name.to_owned()).ok(), Some(action));
+        }
+    }
+
+    #[test]
+    fn the_vim_keymap_moves_bindings_out_of_the_way() {
+        let keys = resolve(Config {
+            keymap: Keymap::Vim,
+            ..Config::default()
+        });
+
+        assert_eq!(key(&keys, Action::Home), "H");
+        assert_eq!(key(&keys, Action::Search), "/");
+        assert_eq!(key(&keys, Action::Quit), "q");
+    }
+
+    #[test]
+    fn configured_keys_override_the_keymap() -> Res<()> {
+        let keys = resolve(Config {
+            keymap: Keymap::Vim,
+            keys: BTreeMap::from([(Action::Home, Binding::try_from("ctrl-h".to_owned())?)]),
+            ..Config::default()
+        });
+
+        assert_eq!(key(&keys, Action::Home), "ctrl-h");
+        assert_eq!(
+            keys.bindings.get(&Action::Home).map(|x| x.event.clone()),
+            Some(Event::CtrlChar('h'))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_vim_keymap_translates_keys() {
+        assert!(keymap_translations(Keymap::Default).is_empty());
+
+        let vim = keymap_translations(Keymap::Vim);
+
+        assert_eq!(vim.len(), VIM_TRANSLATIONS.len());
+        assert!(vim.contains(&(Event::Char('j'), Event::Key(Key::Down))));
+        assert!(vim.contains(&(Event::Char('G'), Event::Key(Key::End))));
+    }
+
+    #[test]
+    fn bindings_parse_named_keys() {
+        let event = |name: &str| Binding::try_from(name.to_owned()).map(|x| x.event).ok();
+
+        assert_eq!(event("Q"), Some(Event::Char('Q')));
+        assert_eq!(event("space"), Some(Event::Char(' ')));
+        assert_eq!(event("PageDown"), Some(Event::Key(Key::PageDown)));
+        assert_eq!(event("ctrl-r"), Some(Event::CtrlChar('r')));
+        assert_eq!(event("f5"), Some(Event::Key(Key::F5)));
+        assert_eq!(event("f13"), None);
+        assert_eq!(event("hyper-x"), None);
+    }
+}
@@ -9,14 +9,33 @@ use color_eyre::Report;
 type Res<T> = Result<T, Report>;
 
 mod app;
+mod cache;
+mod cli;
+mod config;
 mod data;
+mod document;
+mod history;
+mod keys;
+mod migrate;
+mod reader;
+mod rewrite;
 mod scrape;
 mod state;
+mod stats;
+mod sync;
+mod theme;
+mod transfer;
 
 fn main() -> Res<()> {
     color_eyre::install()?;
 
-    app::run()?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.is_empty() {
+        app::run()?;
+    } else {
+        cli::run(&args)?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,163 @@
+//! moving a tracked novel to another site with the same layout, keeping the reading progress
+
+use color_eyre::eyre::bail;
+use log::{error, info};
+use owo_colors::OwoColorize;
+use url::Url;
+
+use crate::{
+    config::Config,
+    data::{Data, LN},
+    rewrite,
+    scrape::{self, SearchResult, Status, BASE_URL},
+    Res,
+};
+
+/// the base urls of every site novels can be migrated between, the built in one first
+pub fn sources(config: &Config) -> Vec<String> {
+    let mut sources = vec![BASE_URL.to_owned()];
+
+    for source in &config.sources {
+        let source = source.trim_end_matches('/');
+
+        if !sources.iter().any(|x| host(x) == host(source)) {
+            sources.push(source.to_owned());
+        }
+    }
+
+    sources
+}
+
+/// the sources a novel could be moved to
+pub fn other_sources(config: &Config, novel: &LN) -> Vec<String> {
+    sources(config)
+        .into_iter()
+        .filter(|x| host(x) != host(&novel.url))
+        .collect()
+}
+
+/// the host a url really goes to, after the rewrite rules
+fn host(url: &str) -> Option<String> {
+    let url = Url::parse(&rewrite::resolve(url)).ok()?;
+    let host = url.host_str()?;
+
+    Some(host.strip_prefix("www.").unwrap_or(host).to_owned())
+}
+
+/// search `source` for novels with the same name
+pub fn candidates(source: &str, novel: &LN) -> Res<Vec<SearchResult>> {
+    Ok(scrape::search_on(source, &novel.name)?.results)
+}
+
+/// find the candidate's chapter matching the one the novel is at, by its title if it's known,
+/// or else by its number
+pub fn map_chapter(novel: &LN, chapter_title: Option<&str>, candidate: &SearchResult) -> usize {
+    let by_title = chapter_title
+        .map(normalize_title)
+        .filter(|x| !x.is_empty())
+        .and_then(|title| {
+            let chapters = scrape::chapter_titles(candidate.url.as_str())
+                .map_err(|e| error!("Failed to get the chapters of {}: {e}", candidate.url))
+                .ok()?;
+
+            chapters
+                .into_iter()
+                .find(|x| normalize_title(&x.1) == title)
+                .map(|x| x.0)
+        });
+
+    by_title.unwrap_or_else(|| {
+        candidate
+            .latest_chapter
+            .map_or(novel.last_chapter, |max| novel.last_chapter.min(max))
+            .max(1)
+    })
+}
+
+/// the title of the chapter the novel is at, from the reading history
+pub fn current_chapter_title(novel: &LN) -> Option<String> {
+    let history = Data::history_of(&novel.url)
+        .map_err(|e| error!("Failed to load the reading history: {e}"))
+        .ok()?;
+
+    history
+        .into_iter()
+        .rev()
+        .find(|x| x.chapter == novel.last_chapter)
+        .map(|x| x.chapter_title)
+}
+
+/// chapter titles without the chapter number, which sites often count differently
+fn normalize_title(title: &str) -> String {
+    let title = title.to_lowercase();
+
+    let words = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+
+    let words = match words.as_slice() {
+        ["chapter", number, rest @ ..] if number.chars().all(|c| c.is_ascii_digit()) => rest,
+        words => words,
+    };
+
+    words.join(" ")
+}
+
+/// point the tracked novel at the candidate, at `chapter`
+pub fn migrate(data: &mut Data, novel: &LN, candidate: &SearchResult, chapter: usize) -> Res<()> {
+    let url = scrape::novel_url(candidate.url.as_str());
+
+    info!(
+        "migrating {} from {} to {} at chapter {}",
+        novel.name.green(),
+        novel.url.yellow(),
+        url.green(),
+        chapter.yellow()
+    );
+
+    let Some(index) = data.tracked().iter().position(|x| x.id == novel.id) else {
+        bail!("{} is not tracked anymore", novel.name.green());
+    };
+
+    let tracked = data.tracked_mut();
+
+    let migrated = LN {
+        status: candidate.status,
+        tags: tracked[index].tags.clone(),
+        ..LN::new(tracked[index].name.clone(), url, chapter)
+    };
+
+    // already tracked at the new source, so fold this one into it rather than tracking it twice
+    if let Some(existing) = tracked
+        .iter_mut()
+        .find(|x| x.id == migrated.id && x.id != novel.id)
+    {
+        info!(
+            "{} is already tracked at {}, merging into it",
+            novel.name.green(),
+            existing.url.green()
+        );
+
+        existing.last_chapter = existing.last_chapter.max(chapter);
+
+        if existing.status == Status::Unknown {
+            existing.status = migrated.status;
+        }
+
+        for tag in migrated.tags {
+            if !existing.tags.contains(&tag) {
+                existing.tags.push(tag);
+            }
+        }
+
+        tracked.remove(index);
+
+        return data.save();
+    }
+
+    // the id changes with the source, so it's a different novel as far as merging goes
+    tracked[index] = migrated;
+
+    data.save()
+}
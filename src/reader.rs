@@ -0,0 +1,706 @@
+//! laying out a chapter's text for the reader
+
+use cursive::theme::{BaseColor, Color, Effect, Style};
+use cursive::utils::lines::spans::LinesIterator;
+use cursive::utils::markup::StyledString;
+use cursive::utils::span::IndexedCow;
+use regex::Regex;
+use std::ops::Range;
+
+use crate::config::{ReaderConfig, TextAlign};
+use crate::document::{Block, Document, Span, SCENE_BREAK};
+
+/// room for the scroll bar and the space before it
+const SCROLLBAR_WIDTH: usize = 2;
+
+/// the narrowest the text can be made
+pub const MIN_WIDTH: usize = 20;
+
+/// how wide the text is, and the margins around it, on a screen `columns` wide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub margin: usize,
+    pub text_width: usize,
+}
+
+pub fn layout(columns: usize, config: &ReaderConfig) -> Layout {
+    let columns = if columns == 0 { 80 } else { columns };
+
+    let Some(width) = config.width else {
+        let margin = columns / 8;
+
+        return Layout {
+            margin,
+            text_width: columns.saturating_sub(margin * 2 + SCROLLBAR_WIDTH),
+        };
+    };
+
+    let text_width = width
+        .max(MIN_WIDTH)
+        .min(columns.saturating_sub(SCROLLBAR_WIDTH));
+
+    Layout {
+        margin: columns.saturating_sub(text_width + SCROLLBAR_WIDTH) / 2,
+        text_width,
+    }
+}
+
+/// a word and the styles of its parts, like an emphasised name with a plain `'s` after it
+type Word = Vec<(String, Style)>;
+
+/// the chapter with its formatting and the configured paragraph spacing, indent and alignment,
+/// for text `width` wide
+pub fn format(document: &Document, config: &ReaderConfig, width: usize) -> StyledString {
+    let justified = config.align == TextAlign::Justified;
+    let separator = "\n".repeat(config.paragraph_spacing + 1);
+    let mut s = StyledString::new();
+
+    for (i, block) in document.blocks.iter().enumerate() {
+        if i > 0 {
+            s.append_plain(&separator);
+        }
+
+        match block {
+            Block::Paragraph(spans) => {
+                let indent = " ".repeat(config.indent);
+
+                if justified {
+                    for (j, line) in wrap(words(spans), width, config.indent).iter().enumerate() {
+                        if j > 0 {
+                            s.append_plain("\n");
+                        }
+
+                        let prefix = if j == 0 { indent.as_str() } else { "" };
+                        append_line(&mut s, prefix, &line.words, width, !line.last);
+                    }
+                } else {
+                    s.append_plain(indent);
+                    append_line(&mut s, "", &words(spans), width, false);
+                }
+            }
+            Block::Heading(spans) => {
+                let heading = words(spans)
+                    .into_iter()
+                    .map(|word| {
+                        word.into_iter()
+                            .map(|(text, style)| (text, style.combine(Effect::Bold)))
+                            .collect()
+                    })
+                    .collect::<Vec<Word>>();
+
+                append_line(&mut s, "", &heading, width, false);
+            }
+            Block::Quote(spans) => {
+                // wrapped here rather than by the view, to put the bar in front of every line
+                let lines = wrap(words(spans), width.saturating_sub(QUOTE_BAR.len()), 0);
+
+                for (j, line) in lines.iter().enumerate() {
+                    if j > 0 {
+                        s.append_plain("\n");
+                    }
+
+                    s.append_styled(QUOTE_BAR, Effect::Dim);
+                    append_line(
+                        &mut s,
+                        "",
+                        &line.words,
+                        width.saturating_sub(QUOTE_BAR.len()),
+                        justified && !line.last,
+                    );
+                }
+            }
+            Block::SceneBreak => {
+                // centred text is centred by the view already
+                let pad = if config.align == TextAlign::Center {
+                    0
+                } else {
+                    width.saturating_sub(SCENE_BREAK.len()) / 2
+                };
+
+                s.append_plain(format!("{}{SCENE_BREAK}", " ".repeat(pad)));
+            }
+        }
+    }
+
+    s
+}
+
+/// what goes in front of each line of a quote
+const QUOTE_BAR: &str = "│ ";
+
+/// the words of `spans`, keeping their styles
+fn words(spans: &[Span]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    // whether the last span ended partway through a word
+    let mut joined = false;
+
+    for span in spans {
+        let style = inline_style(span);
+
+        for (i, part) in span.text.split(' ').enumerate() {
+            if !part.is_empty() {
+                match words.last_mut() {
+                    Some(word) if i == 0 && joined => word.push((part.to_owned(), style)),
+                    _ => words.push(vec![(part.to_owned(), style)]),
+                }
+            }
+        }
+
+        joined = !span.text.ends_with(' ');
+    }
+
+    words
+}
+
+fn inline_style(span: &Span) -> Style {
+    let mut style = Style::none();
+
+    if span.emphasis {
+        style = style.combine(Effect::Italic);
+    }
+
+    if span.strong {
+        style = style.combine(Effect::Bold);
+    }
+
+    style
+}
+
+fn word_width(word: &Word) -> usize {
+    word.iter().map(|(text, _)| text.chars().count()).sum()
+}
+
+/// a line of a paragraph wrapped by [`wrap`]
+struct Line {
+    words: Vec<Word>,
+    /// whether it's the paragraph's last line, which isn't spread out when justified
+    last: bool,
+}
+
+/// break `words` into lines of at most `width`, the first line starting `indent` in
+fn wrap(words: Vec<Word>, width: usize, indent: usize) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut length = 0;
+
+    for word in words {
+        let word_length = word_width(&word);
+
+        match lines.last_mut() {
+            Some(line) if length + 1 + word_length <= width => {
+                line.words.push(word);
+                length += 1 + word_length;
+            }
+            _ => {
+                length = word_length + if lines.is_empty() { indent } else { 0 };
+                lines.push(Line {
+                    words: vec![word],
+                    last: false,
+                });
+            }
+        }
+    }
+
+    if let Some(line) = lines.last_mut() {
+        line.last = true;
+    }
+
+    lines
+}
+
+/// append `words` after `prefix`, with the spaces spread out to fill `width` when `spread`
+fn append_line(s: &mut StyledString, prefix: &str, words: &[Word], width: usize, spread: bool) {
+    if !prefix.is_empty() {
+        s.append_plain(prefix);
+    }
+
+    let gaps = words.len().saturating_sub(1);
+    let letters = words.iter().map(word_width).sum::<usize>() + prefix.len();
+    let spaces = if spread && gaps > 0 {
+        width.saturating_sub(letters).max(gaps)
+    } else {
+        gaps
+    };
+
+    for (j, word) in words.iter().enumerate() {
+        for (text, style) in word {
+            s.append_styled(text, *style);
+        }
+
+        if j < gaps {
+            // the leftmost gaps get the extra spaces
+            let gap = spaces / gaps + usize::from(j < spaces % gaps);
+            s.append_plain(" ".repeat(gap));
+        }
+    }
+}
+
+/// split the formatted chapter into pages of `height` lines, wrapped at `width`
+pub fn pages(text: &StyledString, width: usize, height: usize) -> Vec<StyledString> {
+    let height = height.max(1);
+    let mut pages = Vec::new();
+    let mut page: Vec<StyledString> = Vec::new();
+
+    for row in LinesIterator::new(text, width.max(1)) {
+        let mut pieces = row
+            .segments
+            .iter()
+            .map(|x| (x.resolve_plain(&text), text.spans_raw()[x.span_id].attr))
+            .collect::<Vec<_>>();
+
+        // the spaces a line was wrapped at aren't wanted
+        while let Some((last, _)) = pieces.last_mut() {
+            *last = last.trim_end();
+
+            if !last.is_empty() {
+                break;
+            }
+
+            pieces.pop();
+        }
+
+        // a page shouldn't start with the gap between two paragraphs
+        if page.is_empty() && pieces.is_empty() {
+            continue;
+        }
+
+        let mut line = StyledString::new();
+
+        for (piece, style) in pieces {
+            line.append_styled(piece, style);
+        }
+
+        page.push(line);
+
+        if page.len() == height {
+            pages.push(join_lines(&page));
+            page.clear();
+        }
+    }
+
+    if !page.is_empty() || pages.is_empty() {
+        pages.push(join_lines(&page));
+    }
+
+    pages
+}
+
+fn join_lines(lines: &[StyledString]) -> StyledString {
+    let mut s = StyledString::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            s.append_plain("\n");
+        }
+
+        s.append(line.clone());
+    }
+
+    s
+}
+
+/// how many lines `text` takes up when wrapped at `width`
+pub fn rows(text: &StyledString, width: usize) -> usize {
+    LinesIterator::new(text, width.max(1)).count()
+}
+
+/// how much of `text` there is, in a way that doesn't change when it's wrapped or indented differently
+pub fn length(text: &str) -> usize {
+    text.chars().filter(|x| !x.is_whitespace()).count()
+}
+
+/// the page that `position`, as counted by [`length`], falls on
+pub fn page_at(pages: &[StyledString], position: usize) -> usize {
+    let mut start = 0;
+
+    for (i, page) in pages.iter().enumerate() {
+        start += length(page.source());
+
+        if position < start {
+            return i;
+        }
+    }
+
+    pages.len().saturating_sub(1)
+}
+
+/// where `page` starts, as counted by [`length`]
+pub fn position_of(pages: &[StyledString], page: usize) -> usize {
+    pages.iter().take(page).map(|x| length(x.source())).sum()
+}
+
+/// a search within the chapter
+#[derive(Debug, Clone, Default)]
+pub struct Find {
+    pub query: String,
+    /// which of the matches is the one being looked at
+    pub current: usize,
+}
+
+impl Find {
+    /// go on to the next or previous of `count` matches, going around, or stay on the same one
+    pub fn step(&mut self, count: usize, forward: Option<bool>) {
+        let count = count.max(1);
+
+        self.current = match forward {
+            Some(true) => (self.current + 1) % count,
+            Some(false) => (self.current % count + count - 1) % count,
+            None => self.current % count,
+        };
+    }
+}
+
+/// where `query` is in `text`, ignoring case and taking any run of whitespace, along with the bar
+/// in front of quoted lines, as a single space. that way the plain text of a chapter has the same
+/// matches as the formatted one, whatever its justification, indents and quotes
+pub fn find_in(text: &str, query: &str) -> Vec<Range<usize>> {
+    let words = query
+        .split_whitespace()
+        .map(regex::escape)
+        .collect::<Vec<_>>();
+
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let gap = format!(r"[\s{}]+", QUOTE_BAR.trim());
+
+    Regex::new(&format!("(?i){}", words.join(&gap)))
+        .map(|x| x.find_iter(text).map(|x| x.range()).collect())
+        .unwrap_or_default()
+}
+
+/// the line of `text`, wrapped at `width`, that the byte at `offset` ends up on
+pub fn row_of(text: &StyledString, width: usize, offset: usize) -> usize {
+    let rows = LinesIterator::new(text, width.max(1)).collect::<Vec<_>>();
+
+    rows.iter()
+        .position(|row| {
+            row.segments
+                .last()
+                .and_then(|x| x.source_indices(text.spans_raw()))
+                .is_some_and(|(_, end)| offset < end)
+        })
+        .unwrap_or_else(|| rows.len().saturating_sub(1))
+}
+
+/// `text` with the matches of `find` picked out over its own styles, `first` being the number
+/// of matches before it
+pub fn highlight(text: &StyledString, find: Option<&Find>, first: usize) -> StyledString {
+    let Some(find) = find else {
+        return text.clone();
+    };
+
+    let matches = find_in(text.source(), &find.query)
+        .into_iter()
+        .enumerate()
+        .map(|(i, found)| {
+            let style = if first + i == find.current {
+                Style::from(Effect::Reverse)
+            } else {
+                Style::from(Color::Dark(BaseColor::Yellow)).combine(Effect::Underline)
+            };
+
+            (found.start, found.end, style)
+        })
+        .collect::<Vec<_>>();
+
+    let mut s = StyledString::new();
+
+    for span in text.spans_raw() {
+        let IndexedCow::Borrowed { start, end } = span.content else {
+            continue;
+        };
+
+        let mut at = start;
+
+        // cut the span where matches start and end
+        while at < end {
+            let (next, style) = match matches.iter().find(|x| x.1 > at) {
+                Some(&(from, to, style)) if from <= at => (to.min(end), span.attr.combine(style)),
+                Some(&(from, ..)) => (from.min(end), span.attr),
+                None => (end, span.attr),
+            };
+
+            s.append_styled(&text.source()[at..next], style);
+            at = next;
+        }
+    }
+
+    s
+}
+
+/// one of `pages` with the matches of `find` picked out
+pub fn highlight_page(pages: &[StyledString], page: usize, find: Option<&Find>) -> StyledString {
+    let first = find.map_or(0, |find| {
+        pages
+            .iter()
+            .take(page)
+            .map(|x| find_in(x.source(), &find.query).len())
+            .sum()
+    });
+
+    highlight(&pages[page], find, first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str) -> Span {
+        Span {
+            text: text.to_owned(),
+            ..Span::default()
+        }
+    }
+
+    fn document(blocks: Vec<Block>) -> Document {
+        Document { blocks }
+    }
+
+    fn config(align: TextAlign) -> ReaderConfig {
+        ReaderConfig {
+            align,
+            ..ReaderConfig::default()
+        }
+    }
+
+    #[test]
+    fn layout_takes_a_share_of_the_screen_without_a_width() {
+        let config = ReaderConfig::default();
+
+        assert_eq!(
+            layout(80, &config),
+            Layout {
+                margin: 10,
+                text_width: 58
+            }
+        );
+        // before the screen size is known
+        assert_eq!(layout(0, &config), layout(80, &config));
+    }
+
+    #[test]
+    fn layout_centres_the_configured_width_within_limits() {
+        let width = |width| ReaderConfig {
+            width: Some(width),
+            ..ReaderConfig::default()
+        };
+
+        assert_eq!(
+            layout(100, &width(60)),
+            Layout {
+                margin: 19,
+                text_width: 60
+            }
+        );
+        assert_eq!(layout(100, &width(5)).text_width, MIN_WIDTH);
+        assert_eq!(
+            layout(50, &width(200)),
+            Layout {
+                margin: 0,
+                text_width: 48
+            }
+        );
+    }
+
+    #[test]
+    fn format_spaces_and_indents_paragraphs() {
+        let config = ReaderConfig {
+            paragraph_spacing: 2,
+            indent: 2,
+            ..config(TextAlign::Left)
+        };
+        let text = format(&Document::from_text("one two\n\nthree"), &config, 40);
+
+        assert_eq!(text.source(), "  one two\n\n\n  three");
+    }
+
+    #[test]
+    fn format_justifies_every_line_but_the_last() {
+        let doc = document(vec![Block::Paragraph(vec![span("aa bb cc dd")])]);
+        let text = format(&doc, &config(TextAlign::Justified), 10);
+
+        assert_eq!(text.source(), "aa  bb  cc\ndd");
+    }
+
+    #[test]
+    fn format_puts_a_bar_in_front_of_each_quoted_line() {
+        let doc = document(vec![Block::Quote(vec![span("aa bb")])]);
+        let text = format(&doc, &config(TextAlign::Left), 6);
+
+        assert_eq!(text.source(), "│ aa\n│ bb");
+    }
+
+    #[test]
+    fn format_centres_scene_breaks_unless_the_view_does() {
+        let doc = document(vec![Block::SceneBreak]);
+
+        assert_eq!(
+            format(&doc, &config(TextAlign::Left), 15).source(),
+            format!("     {SCENE_BREAK}")
+        );
+        assert_eq!(
+            format(&doc, &config(TextAlign::Center), 15).source(),
+            SCENE_BREAK
+        );
+    }
+
+    #[test]
+    fn format_keeps_emphasis_and_bolds_headings() {
+        let doc = document(vec![
+            Block::Heading(vec![span("Chapter 1")]),
+            Block::Paragraph(vec![
+                Span {
+                    text: "Yang Kai".to_owned(),
+                    emphasis: true,
+                    strong: false,
+                },
+                span("'s sword"),
+            ]),
+        ]);
+        let text = format(&doc, &config(TextAlign::Left), 40);
+
+        let effects = |word: &str| {
+            text.spans()
+                .find(|x| x.content == word)
+                .map(|x| x.attr.effects)
+                .unwrap_or_default()
+        };
+
+        assert!(effects("Chapter").contains(Effect::Bold));
+        assert!(effects("Kai").contains(Effect::Italic));
+        assert!(!effects("'s").contains(Effect::Italic));
+    }
+
+    fn sources(pages: &[StyledString]) -> Vec<&str> {
+        pages.iter().map(StyledString::source).collect()
+    }
+
+    #[test]
+    fn pages_split_the_text_every_height_lines() {
+        let text = StyledString::plain("one\ntwo\nthree\nfour\nfive");
+
+        assert_eq!(
+            sources(&pages(&text, 10, 2)),
+            ["one\ntwo", "three\nfour", "five"]
+        );
+    }
+
+    #[test]
+    fn pages_drop_wrapping_spaces_and_leading_gaps() {
+        let text = StyledString::plain("aaa bbb\n\ncc");
+
+        assert_eq!(sources(&pages(&text, 4, 2)), ["aaa\nbbb", "cc"]);
+    }
+
+    #[test]
+    fn pages_keep_styles() {
+        let mut text = StyledString::plain("one ");
+        text.append_styled("two", Effect::Italic);
+
+        let pages = pages(&text, 10, 5);
+
+        assert_eq!(sources(&pages), ["one two"]);
+        assert!(pages[0]
+            .spans()
+            .any(|x| x.content == "two" && x.attr.effects.contains(Effect::Italic)));
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_page() {
+        assert_eq!(sources(&pages(&StyledString::new(), 10, 5)), [""]);
+    }
+
+    #[test]
+    fn page_at_finds_the_page_a_position_is_on() {
+        let pages = [StyledString::plain("one two"), StyledString::plain("three")];
+
+        assert_eq!(page_at(&pages, 0), 0);
+        assert_eq!(page_at(&pages, 5), 0);
+        assert_eq!(page_at(&pages, 6), 1);
+        // past the end stays on the last page
+        assert_eq!(page_at(&pages, 100), 1);
+        assert_eq!(page_at(&[], 3), 0);
+    }
+
+    #[test]
+    fn positions_survive_repaging() {
+        let text = StyledString::plain("one two three four five six seven");
+        let narrow = pages(&text, 10, 1);
+        let wide = pages(&text, 20, 1);
+
+        let position = position_of(&narrow, 2);
+
+        assert_eq!(page_at(&narrow, position), 2);
+        assert_eq!(sources(&wide)[page_at(&wide, position)], "five six seven");
+    }
+
+    #[test]
+    fn find_in_ignores_case() {
+        assert_eq!(find_in("Yang Kai met yang kai", "YANG KAI"), [0..8, 13..21]);
+    }
+
+    #[test]
+    fn find_in_matches_across_any_whitespace_and_quote_bars() {
+        let text = "the  quick\n│ brown fox";
+
+        assert_eq!(find_in(text, "quick brown"), std::slice::from_ref(&(5..20)));
+        assert_eq!(
+            find_in(text, " quick   brown "),
+            std::slice::from_ref(&(5..20))
+        );
+    }
+
+    #[test]
+    fn find_in_takes_the_query_literally() {
+        assert_eq!(find_in("a.b axb", "a.b"), std::slice::from_ref(&(0..3)));
+        assert!(find_in("anything", "  ").is_empty());
+    }
+
+    #[test]
+    fn find_steps_around_the_matches() {
+        let mut find = Find::default();
+
+        find.step(3, Some(false));
+        assert_eq!(find.current, 2);
+        find.step(3, Some(true));
+        assert_eq!(find.current, 0);
+        find.current = 5;
+        find.step(3, None);
+        assert_eq!(find.current, 2);
+    }
+
+    #[test]
+    fn highlight_picks_out_the_current_match_over_the_text_styles() {
+        let mut text = StyledString::plain("a cat, ");
+        text.append_styled("a cat", Effect::Italic);
+
+        let find = Find {
+            query: "cat".to_owned(),
+            current: 1,
+        };
+
+        let styles = |first| {
+            highlight(&text, Some(&find), first)
+                .spans()
+                .filter(|x| x.content == "cat")
+                .map(|x| *x.attr)
+                .collect::<Vec<_>>()
+        };
+
+        let found = Style::from(Color::Dark(BaseColor::Yellow)).combine(Effect::Underline);
+        let current = Style::from(Effect::Reverse);
+
+        assert_eq!(
+            styles(0),
+            [found, Style::from(Effect::Italic).combine(current)]
+        );
+        // with a match on an earlier page, the first one here is the current one
+        assert_eq!(
+            styles(1),
+            [current, Style::from(Effect::Italic).combine(found)]
+        );
+        assert_eq!(highlight(&text, Some(&find), 0).source(), text.source());
+        assert_eq!(highlight(&text, None, 0), text);
+    }
+}
@@ -0,0 +1,165 @@
+//! keeping stored urls working when a site moves, through rewrite rules from the config
+//! and the permanent redirects seen while scraping
+
+use log::{error, info};
+use owo_colors::OwoColorize;
+use parking_lot::{const_mutex, Mutex};
+use reqwest::{redirect::Policy, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use url::Url;
+
+use crate::config::Config;
+
+/// how many redirects to follow before giving up, the same as reqwest's default
+const MAX_REDIRECTS: usize = 10;
+
+/// replaces the first occurrence of `from` in a url with `to`, e.g. `freewebnovel.com` with `freewebnovel.net`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RewriteRule {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+}
+
+static RULES: OnceLock<Vec<RewriteRule>> = OnceLock::new();
+static REDIRECTS: Mutex<Vec<Redirect>> = const_mutex(Vec::new());
+
+/// the rewrite rules from the config, read once
+pub fn rules() -> &'static [RewriteRule] {
+    RULES.get_or_init(|| {
+        Config::load().map_or_else(
+            |e| {
+                error!("Failed to load the config, not rewriting urls: {e}");
+                Vec::new()
+            },
+            |x| x.rewrites,
+        )
+    })
+}
+
+/// apply every rule to `url`, in order
+pub fn rewrite(rules: &[RewriteRule], url: &str) -> String {
+    rules
+        .iter()
+        .filter(|x| !x.from.is_empty())
+        .fold(url.to_owned(), |url, rule| {
+            url.replacen(&rule.from, &rule.to, 1)
+        })
+}
+
+/// apply the configured rules to a url before it's requested
+pub fn resolve(url: &str) -> String {
+    rewrite(rules(), url)
+}
+
+/// follow redirects, remembering the permanent ones so the stored urls can be updated
+pub fn redirect_policy() -> Policy {
+    Policy::custom(|attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+
+        let permanent = matches!(
+            attempt.status(),
+            StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+        );
+
+        let moved = attempt
+            .previous()
+            .last()
+            .filter(|from| permanent && is_move(from, attempt.url()));
+
+        if let Some(from) = moved {
+            info!(
+                "permanent redirect from {} to {}",
+                from.as_str().yellow(),
+                attempt.url().as_str().green()
+            );
+
+            REDIRECTS.lock().push(Redirect {
+                from: from.to_string(),
+                to: attempt.url().to_string(),
+            });
+        }
+
+        attempt.follow()
+    })
+}
+
+/// whether a redirect is the site moving rather than, say, a missing chapter sent to the home page:
+/// either only the scheme or host changed, or it still goes to a page of the same novel
+fn is_move(from: &Url, to: &Url) -> bool {
+    let slug = |url: &Url| {
+        url.path_segments()
+            .and_then(|mut x| x.find(|x| !x.is_empty()))
+            .map(|x| x.trim_end_matches(".html").to_owned())
+    };
+
+    (from.path() == to.path() && from.query() == to.query())
+        || slug(from).is_some_and(|x| slug(to) == Some(x))
+}
+
+/// the permanent redirects seen since the last call
+pub fn take_redirects() -> Vec<Redirect> {
+    std::mem::take(&mut *REDIRECTS.lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Res;
+
+    fn rule(from: &str, to: &str) -> RewriteRule {
+        RewriteRule {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        }
+    }
+
+    #[test]
+    fn rules_apply_in_order_once_each() {
+        let rules = [
+            rule("freewebnovel.com", "freewebnovel.net"),
+            rule("", "ignored"),
+            rule("http://", "https://"),
+        ];
+
+        assert_eq!(
+            rewrite(&rules, "http://freewebnovel.com/freewebnovel.com.html"),
+            "https://freewebnovel.net/freewebnovel.com.html"
+        );
+        assert_eq!(rewrite(&[], "https://example.com"), "https://example.com");
+    }
+
+    #[test]
+    fn moves_keep_the_page_or_the_novel() -> Res<()> {
+        let is_move =
+            |from: &str, to: &str| -> Res<bool> { Ok(is_move(&from.parse()?, &to.parse()?)) };
+
+        assert!(is_move(
+            "http://freewebnovel.com/martial-peak/chapter-3.html",
+            "https://freewebnovel.net/martial-peak/chapter-3.html"
+        )?);
+        assert!(is_move(
+            "https://freewebnovel.com/martial-peak.html",
+            "https://freewebnovel.com/martial-peak/"
+        )?);
+        // a missing chapter sent back to the home page
+        assert!(!is_move(
+            "https://freewebnovel.com/martial-peak/chapter-9999.html",
+            "https://freewebnovel.com/"
+        )?);
+        assert!(!is_move(
+            "https://freewebnovel.com/martial-peak.html",
+            "https://freewebnovel.com/martial-god.html"
+        )?);
+
+        Ok(())
+    }
+}
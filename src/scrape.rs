@@ -5,25 +5,120 @@ use log::info;
 use owo_colors::OwoColorize;
 use regex::Regex;
 use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 use url::Url;
+use visdom::types::Elements;
 use visdom::Vis;
 
-use crate::Res;
+use crate::{document::Document, rewrite, Res};
 
-const SEARCH_URL: &str = "https://freewebnovel.com/search/";
+pub const BASE_URL: &str = "https://freewebnovel.com";
+
+/// a client that follows redirects and records the permanent ones
+fn client() -> Res<Client> {
+    Ok(Client::builder()
+        .redirect(rewrite::redirect_policy())
+        .build()?)
+}
+
+/// the url of a novel's main page, from it or any of its chapters
+pub fn novel_url(url: &str) -> String {
+    url.split_once("/chapter")
+        .map_or_else(|| url.to_owned(), |x| format!("{}.html", x.0))
+}
 
 pub fn search(input: &str) -> Res<Search> {
-    let client = Client::new();
+    search_on(BASE_URL, input)
+}
+
+/// search another site with the same layout, `source` being its base url
+pub fn search_on(source: &str, input: &str) -> Res<Search> {
+    let client = client()?;
+
+    let search_url = format!("{}/search/", source.trim_end_matches('/'));
 
     let res = client
-        .post(SEARCH_URL)
+        .post(rewrite::resolve(&search_url))
         .form(&[("searchkey", input)])
         .send()?
         .error_for_status()?;
 
+    let base = res.url().clone();
+    let dom = Vis::load(res.text()?).map_err(|e| eyre!("{}", e.green()))?;
+
+    let results = parse_results(&dom, &base)?;
+    let next_page = next_page(&dom, 1, &base);
+
+    Ok(Search {
+        query: input.to_owned(),
+        catalog: None,
+        results,
+        page: 1,
+        next_page,
+    })
+}
+
+/// fetch the first page of one of the site's novel listings
+pub fn browse(catalog: &Catalog) -> Res<Search> {
+    let client = client()?;
+
+    let res = client
+        .get(rewrite::resolve(&catalog.url()))
+        .send()?
+        .error_for_status()?;
+
+    let base = res.url().clone();
     let dom = Vis::load(res.text()?).map_err(|e| eyre!("{}", e.green()))?;
 
+    let results = parse_results(&dom, &base)?;
+    let next_page = next_page(&dom, 1, &base);
+
+    Ok(Search {
+        query: catalog.to_string(),
+        catalog: Some(catalog.clone()),
+        results,
+        page: 1,
+        next_page,
+    })
+}
+
+/// fetch the next page of a search, appending its results to the ones already loaded
+pub fn search_more(search: &Search) -> Res<Search> {
+    let Some(next) = &search.next_page else {
+        bail!("there are no more results for {}", search.query.green());
+    };
+
+    let client = client()?;
+
+    let res = client
+        .get(rewrite::resolve(next.as_str()))
+        .send()?
+        .error_for_status()?;
+
+    let base = res.url().clone();
+    let dom = Vis::load(res.text()?).map_err(|e| eyre!("{}", e.green()))?;
+
+    let page = search.page + 1;
+    let mut results = search.results.clone();
+
+    for result in parse_results(&dom, &base)? {
+        if !results.iter().any(|x| x.url == result.url) {
+            results.push(result);
+        }
+    }
+
+    Ok(Search {
+        query: search.query.clone(),
+        catalog: search.catalog.clone(),
+        results,
+        page,
+        next_page: next_page(&dom, page, &base),
+    })
+}
+
+/// parse the `div.li-row` entries used by the search and listing pages, with links relative to `base`
+fn parse_results(dom: &Elements, base: &Url) -> Res<Vec<SearchResult>> {
     let elements = dom.find("div.li-row");
 
     let mut results = Vec::new();
@@ -31,41 +126,158 @@ pub fn search(input: &str) -> Res<Search> {
     for el in elements {
         let children = el.children();
         let title = children.find("h3.tit").first();
-        let title = title.text();
+        let title = title.text().trim().to_owned();
         let url = children.find("h3.tit > a").first().attr("href");
 
-        if url.is_none() {
-            bail!("found no results"); // TODO: Nicer error messages
-        }
-
         let Some(url) = url else {
             bail!("found no results"); // TODO: Nicer error messages
         };
 
-        let url = format!("https://freewebnovel.com{}", url.to_string());
+        let url = base.join(&url.to_string())?;
+
+        let author = {
+            let author = labelled_field(&children, "Author").text();
+            let author = author.trim();
 
-        results.push((
-            Url::parse(&url).expect("Failed to parse URL"),
-            title.clone(),
-        ));
+            (!author.is_empty()).then(|| author.to_owned())
+        };
+
+        let genres = labelled_field(&children, "Genre")
+            .find("a")
+            .map(|_, el| el.text().trim().to_owned());
+
+        let latest_chapter = labelled_field(&children, "Chapter")
+            .find("a")
+            .attr("href")
+            .and_then(|x| chapter_number(&x.to_string()));
+
+        let status = Status::parse(&labelled_field(&children, "Status").text());
+
+        results.push(SearchResult {
+            url,
+            title,
+            author,
+            genres,
+            latest_chapter,
+            status,
+        });
     }
 
-    Ok(Search {
-        query: input.to_owned(),
-        results,
-    })
+    Ok(results)
 }
 
-#[derive(Debug)]
+/// find the link to the page after `page` in a `div.pages` paginator
+fn next_page(dom: &Elements, page: usize, base: &Url) -> Option<Url> {
+    let next = (page + 1).to_string();
+
+    let links = dom.find("div.pages a").filter_by(|_, el| {
+        let text = el.text();
+        let text = text.trim();
+
+        text == next || text == ">" || text == ">>" || text.eq_ignore_ascii_case("next")
+    });
+
+    let href = links.first().attr("href")?.to_string();
+
+    base.join(&href).ok()
+}
+
+/// the genres the site has listing pages for
+pub const GENRES: &[&str] = &[
+    "Action",
+    "Adult",
+    "Adventure",
+    "Comedy",
+    "Drama",
+    "Eastern",
+    "Ecchi",
+    "Fantasy",
+    "Game",
+    "Gender Bender",
+    "Harem",
+    "Historical",
+    "Horror",
+    "Josei",
+    "Martial Arts",
+    "Mature",
+    "Mecha",
+    "Mystery",
+    "Psychological",
+    "Reincarnation",
+    "Romance",
+    "School Life",
+    "Sci-fi",
+    "Seinen",
+    "Shoujo",
+    "Shounen",
+    "Slice of Life",
+    "Smut",
+    "Sports",
+    "Supernatural",
+    "Tragedy",
+    "Wuxia",
+    "Xianxia",
+    "Xuanhuan",
+    "Yaoi",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Catalog {
+    LatestRelease,
+    MostPopular,
+    Completed,
+    Genre(String),
+}
+
+impl Catalog {
+    fn url(&self) -> String {
+        match self {
+            Self::LatestRelease => format!("{BASE_URL}/latest-release-novels/"),
+            Self::MostPopular => format!("{BASE_URL}/most-popular-novels/"),
+            Self::Completed => format!("{BASE_URL}/completed-novels/"),
+            Self::Genre(genre) => format!("{BASE_URL}/genres/{}/", genre.replace(' ', "-")),
+        }
+    }
+}
+
+impl std::fmt::Display for Catalog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LatestRelease => write!(f, "Latest Release"),
+            Self::MostPopular => write!(f, "Most Popular"),
+            Self::Completed => write!(f, "Completed"),
+            Self::Genre(genre) => write!(f, "Genre: {genre}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Search {
     pub query: String,
-    pub results: Vec<(Url, String)>,
+    /// set when the results come from a listing page instead of a keyword search
+    pub catalog: Option<Catalog>,
+    pub results: Vec<SearchResult>,
+    pub page: usize,
+    pub next_page: Option<Url>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub url: Url,
+    pub title: String,
+    pub author: Option<String>,
+    pub genres: Vec<String>,
+    pub latest_chapter: Option<usize>,
+    pub status: Status,
 }
 
 pub fn get_name(url: &str) -> Res<String> {
-    let client = Client::new();
+    let client = client()?;
 
-    let res = client.get(url).send()?.error_for_status()?;
+    let res = client
+        .get(rewrite::resolve(url))
+        .send()?
+        .error_for_status()?;
 
     let dom = Vis::load(res.text()?).map_err(|e| eyre!("{}", e.green()))?;
 
@@ -79,9 +291,7 @@ pub fn load(url: &str) -> Res<Output> {
         bail!("invalid url: {}", url.green()); // TODO: Nicer error messages
     }
 
-    let chapter_regex = Regex::new(r"chapter-(\d+)\.html").expect("Failed to create regex");
-
-    let client = Client::new();
+    let client = client()?;
 
     let (main_url, chapter) = {
         let split = url
@@ -102,31 +312,24 @@ pub fn load(url: &str) -> Res<Output> {
     info!("Found main url: {}", main_url.green());
 
     let max_chapters = {
-        let res = client.get(&main_url).send()?.error_for_status()?;
+        let res = client
+            .get(rewrite::resolve(&main_url))
+            .send()?
+            .error_for_status()?;
 
         let dom = Vis::load(res.text()?).map_err(|e| eyre!("{}", e.green()))?;
 
-        let item = dom.find("body > div.main > div > div > div.col-content > div.m-newest1 > ul > li:nth-child(1) > a").attr("href").expect("Failed to get href").to_string();
-
-        let max_chapters = chapter_regex.captures(&item);
-
-        let max_chapters = if let Some(max_chapters) = max_chapters {
-            max_chapters
-                .get(1)
-                .expect("Failed to get max chapters")
-                .as_str()
-        } else {
-            bail!("could not find max chapters, regex failed"); // TODO: Nicer error messages
-        };
-
-        max_chapters.trim().parse::<usize>()?
+        latest_chapter(&dom)?
     };
 
     info!("Found chapter: {}", chapter.yellow());
     info!("Found max chapters: {}", max_chapters.yellow());
 
-    let (name, chapter_title, content) = {
-        let res = client.get(url).send()?.error_for_status()?;
+    let (name, chapter_title, document) = {
+        let res = client
+            .get(rewrite::resolve(url))
+            .send()?
+            .error_for_status()?;
 
         let html = res.text()?;
         let dom = Vis::load(html).map_err(|e| eyre!("{}", e.green()))?;
@@ -144,29 +347,16 @@ pub fn load(url: &str) -> Res<Output> {
         };
         info!("Found chapter title: {}", chapter_title.green());
 
-        let content = {
-            let top = dom.find("#main1 > div > div > div.txt");
-
-            let mut content = String::new();
+        let document = Document::from_html(&dom.find("#main1 > div > div > div.txt"));
 
-            for x in top.children("p") {
-                let text = x.text_content();
-
-                if !text.trim().is_empty() {
-                    write!(&mut content, "{text}\n\n");
-                }
-            }
-
-            content
-        };
-
-        (name, chapter_title, content)
+        (name, chapter_title, document)
     };
 
     Ok(Output {
         name,
         chapter_title,
-        content,
+        content: document.text(),
+        document,
         chapter,
         max_chapters,
     })
@@ -177,41 +367,213 @@ pub fn update_check(url: &str, last_chapter: usize) -> Res<bool> {
         bail!("invalid url: {}", url.green()); // TODO: Nicer error messages
     }
 
-    let chapter_regex = Regex::new(r"chapter-(\d+)\.html").expect("Failed to create regex");
+    let client = client()?;
 
-    let client = Client::new();
-
-    let res = client.get(url).send()?.error_for_status()?;
+    let res = client
+        .get(rewrite::resolve(url))
+        .send()?
+        .error_for_status()?;
 
     let html = res.text()?;
 
     let dom = Vis::load(html).map_err(|e| eyre!("{}", e.green()))?;
 
-    let max_chapters = {
-        let item = dom.find("body > div.main > div > div > div.col-content > div.m-newest1 > ul > li:nth-child(1) > a").attr("href").expect("Failed to get href attribute").to_string();
+    let max_chapters = latest_chapter(&dom)?;
 
-        let max_chapters = chapter_regex.captures(&item);
+    Ok(max_chapters > last_chapter)
+}
 
-        let max_chapters = if let Some(max_chapters) = max_chapters {
-            max_chapters
-                .get(1)
-                .expect("Failed to get max chapters")
-                .as_str()
-        } else {
-            bail!("could not find max chapters, regex failed"); // TODO: Nicer error messages
-        };
+/// find the number of the newest chapter on a novel's main page
+fn latest_chapter(dom: &Elements) -> Res<usize> {
+    let item = dom.find("body > div.main > div > div > div.col-content > div.m-newest1 > ul > li:nth-child(1) > a").attr("href");
 
-        max_chapters.trim().parse::<usize>()?
+    let Some(item) = item else {
+        bail!("could not find the latest chapter link"); // TODO: Nicer error messages
     };
 
-    Ok(max_chapters > last_chapter)
+    let Some(max_chapters) = chapter_number(&item.to_string()) else {
+        bail!("could not find max chapters, regex failed"); // TODO: Nicer error messages
+    };
+
+    Ok(max_chapters)
+}
+
+/// get the chapter number out of a `chapter-N.html` link
+fn chapter_number(href: &str) -> Option<usize> {
+    let chapter_regex = Regex::new(r"chapter-(\d+)\.html").expect("Failed to create regex");
+
+    chapter_regex
+        .captures(href)?
+        .get(1)?
+        .as_str()
+        .trim()
+        .parse::<usize>()
+        .ok()
+}
+
+/// get the contents of one of the labelled `div.item` rows inside `scope`
+fn labelled_field<'a>(scope: &Elements<'a>, label: &str) -> Elements<'a> {
+    scope
+        .find(&format!("div.item > span[title=\"{label}\"]"))
+        .siblings("div.right")
+}
+
+pub fn details(url: &str) -> Res<NovelDetails> {
+    if url.contains("/chapter-") {
+        bail!("invalid url: {}", url.green()); // TODO: Nicer error messages
+    }
+
+    let client = client()?;
+
+    let res = client
+        .get(rewrite::resolve(url))
+        .send()?
+        .error_for_status()?;
+
+    let dom = Vis::load(res.text()?).map_err(|e| eyre!("{}", e.green()))?;
+
+    let name = dom.find("h1.tit").first().text().trim().to_owned();
+    info!("Found title: {}", name.green());
+
+    let info = dom.find("div.m-imgtxt div.txt");
+
+    let alt_titles = labelled_field(&info, "Alternative names")
+        .text()
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(ToOwned::to_owned)
+        .collect();
+
+    let author = {
+        let author = labelled_field(&info, "Author").text();
+        let author = author.trim();
+
+        (!author.is_empty()).then(|| author.to_owned())
+    };
+
+    let genres = labelled_field(&info, "Genre")
+        .find("a")
+        .map(|_, el| el.text().trim().to_owned());
+
+    let status = Status::parse(&labelled_field(&info, "Status").text());
+
+    let synopsis = {
+        let mut synopsis = String::new();
+
+        for x in dom.find("div.m-desc > div.txt > div.inner").children("p") {
+            let text = x.text_content();
+
+            if !text.trim().is_empty() {
+                write!(&mut synopsis, "{}\n\n", text.trim());
+            }
+        }
+
+        synopsis.trim_end().to_owned()
+    };
+
+    let cover = dom
+        .find("div.m-imgtxt > div.pic img")
+        .attr("src")
+        .map(|x| x.to_string());
+
+    let chapters = latest_chapter(&dom)?;
+    info!("Found max chapters: {}", chapters.yellow());
+
+    Ok(NovelDetails {
+        url: url.to_owned(),
+        name,
+        alt_titles,
+        author,
+        genres,
+        status,
+        synopsis,
+        cover,
+        chapters,
+    })
+}
+
+/// the numbers and titles of the chapters listed on a novel's main page
+pub fn chapter_titles(url: &str) -> Res<Vec<(usize, String)>> {
+    let client = client()?;
+
+    let res = client
+        .get(rewrite::resolve(url))
+        .send()?
+        .error_for_status()?;
+
+    let dom = Vis::load(res.text()?).map_err(|e| eyre!("{}", e.green()))?;
+
+    let mut chapters = Vec::new();
+
+    for el in dom.find("ul#idData > li > a") {
+        let Some(number) = el
+            .get_attribute("href")
+            .and_then(|x| chapter_number(&x.to_string()))
+        else {
+            continue;
+        };
+
+        chapters.push((number, el.text().trim().to_owned()));
+    }
+
+    Ok(chapters)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum Status {
+    Ongoing,
+    Completed,
+    #[default]
+    Unknown,
+}
+
+impl Status {
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim().to_lowercase();
+
+        if text.contains("ongoing") {
+            Self::Ongoing
+        } else if text.contains("completed") {
+            Self::Completed
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ongoing => write!(f, "Ongoing"),
+            Self::Completed => write!(f, "Completed"),
+            Self::Unknown => write!(f, "Unknown"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
+pub struct NovelDetails {
+    pub url: String,
+    pub name: String,
+    pub alt_titles: Vec<String>,
+    pub author: Option<String>,
+    pub genres: Vec<String>,
+    pub status: Status,
+    pub synopsis: String,
+    pub cover: Option<String>,
+    pub chapters: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Output {
     pub name: String,
     pub chapter_title: String,
+    /// the chapter as plain text, for counting words and searching
     pub content: String,
+    /// the chapter with its formatting, missing from chapters saved before it was kept
+    #[serde(default)]
+    pub document: Document,
     pub chapter: usize,
     pub max_chapters: usize,
 }
@@ -1,29 +1,61 @@
-use crate::scrape::Output;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    document::Document,
+    reader::Find,
+    scrape::{novel_url, Output},
+};
 
 #[derive(Debug, Clone)]
 pub struct State {
     pub url: String,
+    /// the novel's name
+    pub name: String,
     pub title: String,
     pub chapter: usize,
     pub max_chapters: usize,
     pub content: String,
+    pub document: Document,
+    /// when the reader started showing this chapter, cleared once it's in the history
+    pub opened_at: Option<DateTime<Utc>>,
+    /// how far into the chapter the current page starts, see [`crate::reader::length`]
+    pub position: usize,
+    /// what's being searched for in the chapter, if anything
+    pub find: Option<Find>,
 }
 
 impl State {
     pub fn from_output(url: &str, output: Output) -> Self {
         let Output {
+            name,
             chapter_title,
             content,
+            document,
             chapter,
             max_chapters,
-            ..
         } = output;
+        let document = if document.is_empty() {
+            Document::from_text(&content)
+        } else {
+            document
+        };
+
         Self {
             url: url.to_owned(),
+            name,
             title: chapter_title,
             chapter,
             max_chapters,
             content,
+            document,
+            opened_at: None,
+            position: 0,
+            find: None,
         }
     }
+
+    /// the url of the novel's main page
+    pub fn novel_url(&self) -> String {
+        novel_url(&self.url)
+    }
 }
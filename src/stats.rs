@@ -0,0 +1,305 @@
+//! reading statistics, worked out from the reading history
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    data::{novel_id, LN},
+    history::HistoryEntry,
+};
+
+/// chapters open for less than this are treated as skipped through, and don't count towards reading speed
+const MIN_READING_SECONDS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub chapters: usize,
+    pub words: usize,
+    pub seconds: u64,
+}
+
+impl Totals {
+    const fn add(&mut self, entry: &HistoryEntry) {
+        self.chapters += 1;
+        self.words += entry.words;
+        self.seconds += entry.seconds;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NovelProgress {
+    pub name: String,
+    pub chapter: usize,
+    /// the chapter count the last time the novel was read, if it ever was
+    pub max_chapters: Option<usize>,
+}
+
+impl NovelProgress {
+    pub fn percent(&self) -> Option<usize> {
+        self.max_chapters
+            .filter(|&max| max > 0)
+            .map(|max| (self.chapter * 100 / max).min(100))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub total: Totals,
+    pub days: BTreeMap<NaiveDate, Totals>,
+    /// keyed by the monday starting the week
+    pub weeks: BTreeMap<NaiveDate, Totals>,
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    /// reading speed, over the chapters that were actually read
+    pub words_per_minute: Option<usize>,
+    pub minutes_per_chapter: Option<usize>,
+    pub novels: Vec<NovelProgress>,
+}
+
+impl Stats {
+    pub fn new(history: &[HistoryEntry], tracked: &[LN], today: NaiveDate) -> Self {
+        let mut stats = Self::default();
+        let mut read = Totals::default();
+
+        for entry in history {
+            let day = entry.opened_at.with_timezone(&Local).date_naive();
+
+            stats.total.add(entry);
+            stats.days.entry(day).or_default().add(entry);
+            stats.weeks.entry(week_of(day)).or_default().add(entry);
+
+            if entry.seconds >= MIN_READING_SECONDS {
+                read.add(entry);
+            }
+        }
+
+        if read.seconds > 0 {
+            let seconds = usize::try_from(read.seconds).unwrap_or(usize::MAX);
+
+            stats.words_per_minute =
+                (read.words > 0).then(|| read.words.saturating_mul(60) / seconds);
+            stats.minutes_per_chapter = Some(seconds / 60 / read.chapters);
+        }
+
+        (stats.current_streak, stats.longest_streak) = streaks(&stats.days, today);
+        stats.novels = progress(history, tracked);
+
+        stats
+    }
+
+    /// the totals for each of the last `count` days, oldest first, including days without reading
+    pub fn last_days(&self, today: NaiveDate, count: usize) -> Vec<(NaiveDate, Totals)> {
+        (0..count)
+            .rev()
+            .filter_map(|x| today.checked_sub_signed(Duration::days(i64::try_from(x).ok()?)))
+            .map(|day| (day, self.days.get(&day).copied().unwrap_or_default()))
+            .collect()
+    }
+
+    /// the totals for each of the last `count` weeks, oldest first
+    pub fn last_weeks(&self, today: NaiveDate, count: usize) -> Vec<(NaiveDate, Totals)> {
+        let this_week = week_of(today);
+
+        (0..count)
+            .rev()
+            .filter_map(|x| this_week.checked_sub_signed(Duration::weeks(i64::try_from(x).ok()?)))
+            .map(|week| (week, self.weeks.get(&week).copied().unwrap_or_default()))
+            .collect()
+    }
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+fn week_of(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(i64::from(day.weekday().num_days_from_monday()))
+}
+
+/// the current and longest run of consecutive days with reading. today not having any reading yet doesn't break the current streak
+fn streaks(days: &BTreeMap<NaiveDate, Totals>, today: NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &day in days.keys() {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let yesterday = today.pred_opt();
+
+    let current = match previous {
+        Some(last) if last == today || Some(last) == yesterday => run,
+        _ => 0,
+    };
+
+    (current, longest)
+}
+
+/// how far along each tracked novel is, using the chapter counts seen while reading
+fn progress(history: &[HistoryEntry], tracked: &[LN]) -> Vec<NovelProgress> {
+    let mut max_chapters: HashMap<String, (DateTime<Utc>, usize)> = HashMap::new();
+
+    for entry in history.iter().filter(|x| x.max_chapters > 0) {
+        let latest = max_chapters
+            .entry(novel_id(&entry.url))
+            .or_insert((entry.opened_at, entry.max_chapters));
+
+        if entry.opened_at >= latest.0 {
+            *latest = (entry.opened_at, entry.max_chapters);
+        }
+    }
+
+    tracked
+        .iter()
+        .map(|novel| NovelProgress {
+            name: novel.name.clone(),
+            chapter: novel.last_chapter,
+            // chapters read since a rewrite are under the id of the new url, so the latest of both
+            max_chapters: [novel.id.clone(), novel_id(&novel.url)]
+                .iter()
+                .filter_map(|x| max_chapters.get(x))
+                .max_by_key(|x| x.0)
+                .map(|x| x.1),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const URL: &str = "https://freewebnovel.com/martial-peak.html";
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap_or_default()
+    }
+
+    /// a chapter read at noon local time on the `d`th of october
+    fn entry(d: u32, seconds: u64, words: usize, max_chapters: usize) -> HistoryEntry {
+        HistoryEntry {
+            name: "Martial Peak".to_owned(),
+            url: URL.to_owned(),
+            chapter: 1,
+            chapter_title: String::new(),
+            opened_at: Local
+                .with_ymd_and_hms(2026, 10, d, 12, 0, 0)
+                .single()
+                .map(|x| x.to_utc())
+                .unwrap_or_default(),
+            seconds,
+            words,
+            max_chapters,
+        }
+    }
+
+    fn days(days: &[u32]) -> BTreeMap<NaiveDate, Totals> {
+        days.iter().map(|&d| (day(d), Totals::default())).collect()
+    }
+
+    #[test]
+    fn streaks_count_consecutive_days() {
+        let days = days(&[1, 2, 3, 5, 6]);
+
+        assert_eq!(streaks(&days, day(6)), (2, 3));
+        // nothing read yet today doesn't end the streak, but missing yesterday does
+        assert_eq!(streaks(&days, day(7)), (2, 3));
+        assert_eq!(streaks(&days, day(8)), (0, 3));
+        assert_eq!(streaks(&BTreeMap::new(), day(8)), (0, 0));
+    }
+
+    #[test]
+    fn history_adds_up_by_day_and_week() {
+        // the 5th and 12th are mondays
+        let history = [
+            entry(5, 600, 3000, 0),
+            entry(11, 600, 3000, 0),
+            entry(12, 10, 5000, 0),
+        ];
+
+        let stats = Stats::new(&history, &[], day(12));
+
+        assert_eq!(stats.total.chapters, 3);
+        assert_eq!(stats.total.words, 11000);
+        assert_eq!(stats.weeks.get(&day(5)).map(|x| x.chapters), Some(2));
+        assert_eq!(stats.weeks.get(&day(12)).map(|x| x.chapters), Some(1));
+        assert_eq!((stats.current_streak, stats.longest_streak), (2, 2));
+
+        assert_eq!(
+            stats.last_days(day(12), 3),
+            [
+                (day(10), Totals::default()),
+                (
+                    day(11),
+                    Totals {
+                        chapters: 1,
+                        words: 3000,
+                        seconds: 600
+                    }
+                ),
+                (
+                    day(12),
+                    Totals {
+                        chapters: 1,
+                        words: 5000,
+                        seconds: 10
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            stats
+                .last_weeks(day(14), 2)
+                .iter()
+                .map(|x| x.0)
+                .collect::<Vec<_>>(),
+            [day(5), day(12)]
+        );
+    }
+
+    #[test]
+    fn skimmed_chapters_dont_count_towards_reading_speed() {
+        let history = [
+            entry(5, 600, 3000, 0),
+            entry(5, 600, 3000, 0),
+            entry(5, 10, 5000, 0),
+        ];
+
+        let stats = Stats::new(&history, &[], day(5));
+
+        assert_eq!(stats.words_per_minute, Some(300));
+        assert_eq!(stats.minutes_per_chapter, Some(10));
+        assert_eq!(Stats::new(&[], &[], day(5)).words_per_minute, None);
+    }
+
+    #[test]
+    fn progress_uses_the_latest_chapter_count() {
+        let history = [
+            entry(6, 600, 0, 80),
+            entry(5, 600, 0, 100),
+            entry(4, 600, 0, 0),
+        ];
+        let tracked = [
+            LN::new("Martial Peak".to_owned(), URL.to_owned(), 20),
+            LN::new(
+                "Martial God".to_owned(),
+                "https://freewebnovel.com/martial-god.html".to_owned(),
+                3,
+            ),
+        ];
+
+        let novels = progress(&history, &tracked);
+
+        assert_eq!(novels[0].max_chapters, Some(80));
+        assert_eq!(novels[0].percent(), Some(25));
+        assert_eq!(novels[1].max_chapters, None);
+        assert_eq!(novels[1].percent(), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! syncing the library with a copy kept in a folder or a git repository
+
+use color_eyre::eyre::bail;
+use log::info;
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{
+    config::{RemoteKind, SyncConfig},
+    data::{fix_ids, merge_tracked, Data, Untracked, LN},
+    Res,
+};
+
+/// the file the library is kept in on the remote
+const SYNC_FILE: &str = "lncli-sync.json";
+
+/// bumped whenever the layout of [`SyncFile`] changes
+const SYNC_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct SyncFile {
+    version: u64,
+    tracked: Vec<LN>,
+    #[serde(default)]
+    untracked: Vec<Untracked>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remote {
+    Folder(PathBuf),
+    Git(String),
+}
+
+impl Remote {
+    /// pick the remote from the command line or the config, guessing its kind if needed
+    pub fn new(config: &SyncConfig, remote: Option<String>) -> Res<Self> {
+        let Some(remote) = remote.or_else(|| config.remote.clone()) else {
+            bail!(
+                "no remote to sync with, set `sync.remote` in {} or pass `--remote`",
+                crate::config::Config::path().display().to_string().green()
+            );
+        };
+
+        let kind = config.kind.unwrap_or_else(|| {
+            let git_like = remote.starts_with("git@")
+                || remote.contains("://")
+                || Path::new(&remote)
+                    .extension()
+                    .is_some_and(|x| x.eq_ignore_ascii_case("git"));
+
+            if git_like {
+                RemoteKind::Git
+            } else {
+                RemoteKind::Folder
+            }
+        });
+
+        Ok(match kind {
+            RemoteKind::Folder => Self::Folder(PathBuf::from(remote)),
+            RemoteKind::Git => Self::Git(remote),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub added: usize,
+    pub removed: usize,
+    pub updated: usize,
+}
+
+/// merge the local library with the one on the remote, and write the result to both
+pub fn sync(remote: &Remote) -> Res<Summary> {
+    let folder = match remote {
+        Remote::Folder(folder) => {
+            std::fs::create_dir_all(folder)?;
+            folder.clone()
+        }
+        Remote::Git(url) => pull(url)?,
+    };
+
+    let path = folder.join(SYNC_FILE);
+
+    let theirs = if path.exists() {
+        let mut theirs: SyncFile = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+
+        if theirs.version > SYNC_VERSION {
+            bail!(
+                "the remote was written by a newer lncli (version {})",
+                theirs.version.yellow()
+            );
+        }
+
+        fix_ids(&mut theirs.tracked, &mut theirs.untracked);
+
+        theirs
+    } else {
+        SyncFile {
+            version: SYNC_VERSION,
+            tracked: Vec::new(),
+            untracked: Vec::new(),
+        }
+    };
+
+    let mut data = if Data::exists()? {
+        Data::load()?
+    } else {
+        Data::new()
+    };
+
+    let before = data.tracked().clone();
+
+    let (mut tracked, untracked) = merge_tracked(
+        (data.tracked(), data.untracked()),
+        (&theirs.tracked, &theirs.untracked),
+    );
+
+    // whatever else changed, keep the furthest chapter read on any machine
+    for novel in &mut tracked {
+        let furthest = before
+            .iter()
+            .chain(&theirs.tracked)
+            .filter(|x| x.id == novel.id)
+            .max_by_key(|x| x.last_chapter);
+
+        if let Some(furthest) = furthest {
+            if furthest.last_chapter > novel.last_chapter {
+                novel.last_chapter = furthest.last_chapter;
+                novel.stamps.last_chapter = furthest.stamps.last_chapter;
+            }
+        }
+    }
+
+    let summary = Summary {
+        added: tracked
+            .iter()
+            .filter(|x| !before.iter().any(|b| b.id == x.id))
+            .count(),
+        removed: before
+            .iter()
+            .filter(|b| !tracked.iter().any(|x| x.id == b.id))
+            .count(),
+        updated: tracked
+            .iter()
+            .filter(|x| before.iter().any(|b| b.id == x.id && b != *x))
+            .count(),
+    };
+
+    data.set_merged(tracked.clone(), untracked.clone());
+    data.save()?;
+
+    let ours = SyncFile {
+        version: SYNC_VERSION,
+        tracked,
+        untracked,
+    };
+
+    let temp = path.with_extension("json.tmp");
+
+    std::fs::write(&temp, serde_json::to_string_pretty(&ours)?)?;
+    std::fs::rename(&temp, &path)?;
+
+    if let Remote::Git(_) = remote {
+        push(&folder)?;
+    }
+
+    Ok(summary)
+}
+
+/// run git in `dir`, returning its output
+fn git(dir: &Path, args: &[&str]) -> Res<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed: {}",
+            args.join(" ").yellow(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// clone the repository the first time, and bring it up to date every time after
+fn pull(url: &str) -> Res<PathBuf> {
+    let folder = Data::data_folder().join("sync/");
+
+    if !folder.join(".git").exists() {
+        info!("cloning {}", url.green());
+
+        std::fs::create_dir_all(&folder)?;
+        git(&folder, &["clone", url, "."])?;
+
+        return Ok(folder);
+    }
+
+    // a freshly created remote has no branches to pull from yet
+    if !git(&folder, &["ls-remote", "--heads", "origin"])?
+        .trim()
+        .is_empty()
+    {
+        git(&folder, &["pull", "--rebase"])?;
+    }
+
+    Ok(folder)
+}
+
+fn push(folder: &Path) -> Res<()> {
+    git(folder, &["add", SYNC_FILE])?;
+
+    if git(folder, &["status", "--porcelain", SYNC_FILE])?
+        .trim()
+        .is_empty()
+    {
+        info!("nothing changed, not pushing");
+        return Ok(());
+    }
+
+    git(folder, &["commit", "-m", "Sync library"])?;
+    git(folder, &["push", "origin", "HEAD"])?;
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! themes: the built in one, a few bundled ones, and any in the `themes` folder of the data folder
+
+use color_eyre::eyre::{bail, eyre};
+use cursive::theme::{BaseColor, BorderStyle, Color, Error, Palette, PaletteColor, Theme};
+use owo_colors::OwoColorize;
+use std::path::PathBuf;
+
+use crate::{config::Config, data::Data, Res};
+
+pub const DEFAULT: &str = "default";
+
+const BUNDLED: &[(&str, &str)] = &[
+    ("dark", include_str!("../themes/dark.toml")),
+    ("light", include_str!("../themes/light.toml")),
+    ("sepia", include_str!("../themes/sepia.toml")),
+    (
+        "high-contrast",
+        include_str!("../themes/high-contrast.toml"),
+    ),
+];
+
+/// where user themes go, as `<name>.toml` files in cursive's theme format
+pub fn folder() -> PathBuf {
+    Data::data_folder().join("themes")
+}
+
+/// every theme's name, the built in one first. a user theme with the same name as a bundled one replaces it
+pub fn names() -> Vec<String> {
+    let mut names = vec![DEFAULT.to_owned()];
+
+    names.extend(BUNDLED.iter().map(|x| x.0.to_owned()));
+
+    let mut user = std::fs::read_dir(folder())
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|x| x.path())
+        .filter(|x| x.extension().is_some_and(|x| x == "toml"))
+        .filter_map(|x| Some(x.file_stem()?.to_string_lossy().to_string()))
+        .filter(|x| !names.contains(x))
+        .collect::<Vec<_>>();
+
+    user.sort();
+    names.extend(user);
+
+    names
+}
+
+pub fn load(name: &str) -> Res<Theme> {
+    let path = folder().join(format!("{name}.toml"));
+
+    if path.exists() {
+        return cursive::theme::load_theme_file(&path).map_err(|e| {
+            eyre!(
+                "failed to load theme {}: {}",
+                path.display().green(),
+                describe(e)
+            )
+        });
+    }
+
+    if name == DEFAULT {
+        return Ok(default());
+    }
+
+    let Some((_, toml)) = BUNDLED.iter().find(|x| x.0 == name) else {
+        bail!("there is no theme called {}", name.yellow());
+    };
+
+    cursive::theme::load_toml(toml)
+        .map_err(|e| eyre!("failed to load theme {}: {}", name.yellow(), describe(e)))
+}
+
+/// the configured theme, falling back to the default one along with why
+pub fn configured() -> (Theme, Option<color_eyre::Report>) {
+    let name = match Config::load() {
+        Ok(config) => config.theme,
+        Err(e) => return (default(), Some(e)),
+    };
+
+    let Some(name) = name else {
+        return (default(), None);
+    };
+
+    match load(&name) {
+        Ok(theme) => (theme, None),
+        Err(e) => (default(), Some(e)),
+    }
+}
+
+fn describe(error: Error) -> String {
+    match error {
+        Error::Io(e) => e.to_string(),
+        Error::Parse(e) => e.to_string(),
+    }
+}
+
+pub fn default() -> Theme {
+    let mut t = Theme {
+        shadow: false,
+        borders: BorderStyle::Simple,
+        palette: Palette::default(),
+    };
+
+    t.palette[PaletteColor::Background] = Color::Dark(BaseColor::Black);
+    t.palette[PaletteColor::View] = Color::TerminalDefault;
+    t.palette[PaletteColor::Primary] = Color::Light(BaseColor::White);
+
+    t.palette[PaletteColor::Secondary] = Color::Light(BaseColor::Black);
+    t.palette[PaletteColor::Highlight] = Color::Dark(BaseColor::Black);
+    t.palette[PaletteColor::HighlightText] = Color::Light(BaseColor::White);
+    t.palette[PaletteColor::HighlightInactive] = Color::Light(BaseColor::Black);
+
+    t
+}
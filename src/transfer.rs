@@ -0,0 +1,471 @@
+//! import and export of the library, in lncli's own format and the ones other readers use
+
+use chrono::Local;
+use color_eyre::eyre::{bail, eyre};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+use url::Url;
+
+use crate::{
+    data::{fix_ids, Data, LN},
+    scrape::{self, Status},
+    Res,
+};
+
+/// bumped whenever the layout of [`Export`] changes
+const EXPORT_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// lncli's own portable export
+    Json,
+    Csv,
+    /// a Tachiyomi/Mihon-style JSON backup list, import only
+    Tachiyomi,
+    /// one novel or chapter url per line, import only
+    UrlList,
+}
+
+impl Format {
+    /// guess the format from a file's extension
+    pub fn guess(path: &Path) -> Self {
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("csv") => Self::Csv,
+            Some("txt" | "list") => Self::UrlList,
+            _ => Self::Json,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" | "lncli" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "tachiyomi" | "mihon" => Ok(Self::Tachiyomi),
+            "urls" | "url-list" | "txt" => Ok(Self::UrlList),
+            _ => Err(eyre!(
+                "unknown format: {} (expected json, csv, tachiyomi or urls)",
+                s.red()
+            )),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Csv => write!(f, "csv"),
+            Self::Tachiyomi => write!(f, "tachiyomi"),
+            Self::UrlList => write!(f, "urls"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Export {
+    #[serde(rename = "lncli_export")]
+    version: u64,
+    exported_at: String,
+    tracked: Vec<LN>,
+    #[serde(default)]
+    recent: Vec<LN>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct CsvRow {
+    name: String,
+    url: String,
+    last_chapter: usize,
+    #[serde(default)]
+    status: String,
+    /// separated by `;`
+    #[serde(default)]
+    tags: String,
+}
+
+/// write the library to `path`, returning how many novels were exported
+pub fn export(data: &Data, format: Format, path: &Path) -> Res<usize> {
+    match format {
+        Format::Json => {
+            let export = Export {
+                version: EXPORT_VERSION,
+                exported_at: Local::now().to_rfc3339(),
+                tracked: data.tracked().clone(),
+                recent: data.recent().iter().cloned().collect(),
+            };
+
+            std::fs::write(path, serde_json::to_string_pretty(&export)?)?;
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_path(path)?;
+
+            for novel in data.tracked() {
+                writer.serialize(CsvRow {
+                    name: novel.name.clone(),
+                    url: novel.url.clone(),
+                    last_chapter: novel.last_chapter,
+                    status: novel.status.to_string(),
+                    tags: novel.tags.join(";"),
+                })?;
+            }
+
+            writer.flush()?;
+        }
+        Format::Tachiyomi | Format::UrlList => {
+            bail!("the {} format can only be imported", format.yellow());
+        }
+    }
+
+    Ok(data.tracked().len())
+}
+
+/// read the novels in `path`
+pub fn import(format: Format, path: &Path) -> Res<Vec<LN>> {
+    parse(format, &std::fs::read_to_string(path)?)
+}
+
+/// read the novels in the contents of a file in `format`
+fn parse(format: Format, contents: &str) -> Res<Vec<LN>> {
+    match format {
+        Format::Json => {
+            let mut export: Export = serde_json::from_str(contents)?;
+
+            if export.version > EXPORT_VERSION {
+                bail!(
+                    "export was made by a newer lncli (version {})",
+                    export.version.yellow()
+                );
+            }
+
+            fix_ids(&mut export.tracked, &mut []);
+
+            Ok(export.tracked)
+        }
+        Format::Csv => csv::Reader::from_reader(contents.as_bytes())
+            .deserialize::<CsvRow>()
+            .map(|row| {
+                let row = row?;
+
+                Ok(LN {
+                    status: Status::parse(&row.status),
+                    tags: row
+                        .tags
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect(),
+                    ..LN::new(row.name, absolute_url(&row.url)?, row.last_chapter.max(1))
+                })
+            })
+            .collect(),
+        Format::Tachiyomi => import_tachiyomi(contents),
+        Format::UrlList => contents
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty() && !x.starts_with('#'))
+            .map(|line| {
+                let (url, chapter) = split_chapter_url(&absolute_url(line)?);
+
+                Ok(LN::new(name_from_url(&url), url, chapter.unwrap_or(1)))
+            })
+            .collect(),
+    }
+}
+
+/// read a `backupManga` list, or a bare list of entries, with at least a title and url each
+fn import_tachiyomi(contents: &str) -> Res<Vec<LN>> {
+    let value: Value = serde_json::from_str(contents)?;
+
+    let entries = value
+        .get("backupManga")
+        .unwrap_or(&value)
+        .as_array()
+        .ok_or_else(|| eyre!("expected a list of entries, or a `backupManga` list"))?;
+
+    let mut novels = Vec::new();
+
+    for entry in entries {
+        let title = entry
+            .get("title")
+            .or_else(|| entry.get("name"))
+            .and_then(Value::as_str);
+        let url = entry.get("url").and_then(Value::as_str);
+
+        let (Some(title), Some(url)) = (title, url) else {
+            bail!(
+                "entry is missing a title or url: {}",
+                entry.to_string().red()
+            );
+        };
+
+        let (url, chapter) = split_chapter_url(&absolute_url(url)?);
+
+        // the highest chapter marked as read, if the backup has chapters in it
+        let read = entry
+            .get("chapters")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|x| x.get("read").and_then(Value::as_bool).unwrap_or(false))
+            .filter_map(|x| x.get("chapterNumber").and_then(Value::as_f64))
+            .fold(None, |acc: Option<f64>, x| {
+                Some(acc.map_or(x, |acc| acc.max(x)))
+            });
+
+        let last_chapter = read
+            .and_then(|x| format!("{}", x.floor()).parse::<usize>().ok())
+            .or(chapter)
+            .unwrap_or(1)
+            .max(1);
+
+        // tachiyomi uses 1 for ongoing and 2 for completed
+        let status = match entry.get("status").and_then(Value::as_u64) {
+            Some(1) => Status::Ongoing,
+            Some(2) => Status::Completed,
+            _ => Status::Unknown,
+        };
+
+        novels.push(LN {
+            status,
+            ..LN::new(title.to_owned(), url, last_chapter)
+        });
+    }
+
+    Ok(novels)
+}
+
+/// add imported novels to the tracked list, returning how many were added and updated
+pub fn merge(data: &mut Data, novels: Vec<LN>) -> (usize, usize) {
+    let mut added = 0;
+    let mut updated = 0;
+
+    for novel in novels {
+        let existing = data.tracked_mut().iter_mut().find(|x| x.id == novel.id);
+
+        let Some(existing) = existing else {
+            data.add_tracked(novel);
+            added += 1;
+            continue;
+        };
+
+        let before = existing.clone();
+
+        existing.last_chapter = existing.last_chapter.max(novel.last_chapter);
+
+        if existing.status == Status::Unknown {
+            existing.status = novel.status;
+        }
+
+        for tag in novel.tags {
+            if !existing.tags.contains(&tag) {
+                existing.tags.push(tag);
+            }
+        }
+
+        if *existing != before {
+            updated += 1;
+        }
+    }
+
+    (added, updated)
+}
+
+/// make relative urls (as some backups store them) absolute
+fn absolute_url(url: &str) -> Res<String> {
+    Ok(Url::parse(scrape::BASE_URL)?.join(url)?.to_string())
+}
+
+/// split a chapter url into the novel's url and the chapter number
+fn split_chapter_url(url: &str) -> (String, Option<usize>) {
+    let Some((main, chapter)) = url.split_once("/chapter-") else {
+        return (url.to_owned(), None);
+    };
+
+    let chapter = chapter.trim_end_matches(".html").parse::<usize>().ok();
+
+    (format!("{main}.html"), chapter)
+}
+
+/// make a readable name out of a url slug, for formats that don't carry one
+fn name_from_url(url: &str) -> String {
+    let slug = url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(url)
+        .trim_end_matches(".html");
+
+    slug.split('-')
+        .filter(|x| !x.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().chain(chars).collect::<String>()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://freewebnovel.com/martial-peak.html";
+
+    #[test]
+    fn formats_are_guessed_and_named() -> Res<()> {
+        assert_eq!(Format::guess(Path::new("library.csv")), Format::Csv);
+        assert_eq!(Format::guess(Path::new("urls.txt")), Format::UrlList);
+        assert_eq!(Format::guess(Path::new("library")), Format::Json);
+        assert_eq!("Mihon".parse::<Format>()?, Format::Tachiyomi);
+        assert!("epub".parse::<Format>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_csv_rows() -> Res<()> {
+        let csv = "name,url,last_chapter,status,tags\n\
+                   Martial Peak,/martial-peak.html,12,Completed,cultivation; favourite\n\
+                   Martial God,https://freewebnovel.com/martial-god.html,0,,\n";
+
+        let novels = parse(Format::Csv, csv)?;
+
+        assert_eq!(novels.len(), 2);
+        assert_eq!(novels[0].url, URL);
+        assert_eq!(novels[0].last_chapter, 12);
+        assert_eq!(novels[0].status, Status::Completed);
+        assert_eq!(novels[0].tags, ["cultivation", "favourite"]);
+        assert_eq!(novels[1].last_chapter, 1);
+        assert!(novels[1].tags.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_url_lists() -> Res<()> {
+        let list = "# to read\n\n/martial-peak/chapter-40.html\n  https://freewebnovel.com/martial-god.html  \n";
+
+        let novels = parse(Format::UrlList, list)?;
+
+        assert_eq!(novels.len(), 2);
+        assert_eq!(novels[0].name, "Martial Peak");
+        assert_eq!(novels[0].url, URL);
+        assert_eq!(novels[0].last_chapter, 40);
+        assert_eq!(novels[1].name, "Martial God");
+        assert_eq!(novels[1].last_chapter, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_tachiyomi_backups() -> Res<()> {
+        let backup = r#"{ "backupManga": [{
+            "title": "Martial Peak",
+            "url": "/martial-peak.html",
+            "status": 2,
+            "chapters": [
+                { "chapterNumber": 3.0, "read": true },
+                { "chapterNumber": 7.5, "read": true },
+                { "chapterNumber": 9.0, "read": false }
+            ]
+        }] }"#;
+
+        let novels = parse(Format::Tachiyomi, backup)?;
+
+        assert_eq!(novels.len(), 1);
+        assert_eq!(novels[0].url, URL);
+        assert_eq!(novels[0].last_chapter, 7);
+        assert_eq!(novels[0].status, Status::Completed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_bare_tachiyomi_lists() -> Res<()> {
+        let list =
+            r#"[{ "name": "Martial Peak", "url": "/martial-peak/chapter-5.html", "status": 1 }]"#;
+
+        let novels = import_tachiyomi(list)?;
+
+        assert_eq!(novels[0].url, URL);
+        assert_eq!(novels[0].last_chapter, 5);
+        assert_eq!(novels[0].status, Status::Ongoing);
+
+        assert!(import_tachiyomi(r#"[{ "url": "/martial-peak.html" }]"#).is_err());
+        assert!(import_tachiyomi(r#"{ "title": "Martial Peak" }"#).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_exports_from_newer_versions() {
+        let export = r#"{ "lncli_export": 99, "exported_at": "", "tracked": [] }"#;
+
+        assert!(parse(Format::Json, export).is_err());
+    }
+
+    #[test]
+    fn chapter_urls_split_into_the_novel_and_chapter() {
+        assert_eq!(
+            split_chapter_url("https://freewebnovel.com/martial-peak/chapter-12.html"),
+            (URL.to_owned(), Some(12))
+        );
+        assert_eq!(split_chapter_url(URL), (URL.to_owned(), None));
+        assert_eq!(
+            split_chapter_url("https://freewebnovel.com/martial-peak/chapter-x.html"),
+            (URL.to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn names_come_from_the_url_slug() -> Res<()> {
+        assert_eq!(name_from_url(URL), "Martial Peak");
+        assert_eq!(
+            name_from_url("https://freewebnovel.com/the--first-order/"),
+            "The First Order"
+        );
+        assert_eq!(absolute_url("/martial-peak.html")?, URL);
+        assert_eq!(absolute_url(URL)?, URL);
+
+        Ok(())
+    }
+
+    #[test]
+    fn merging_keeps_the_furthest_chapter_and_adds_tags() {
+        let mut data = Data::new();
+        data.add_tracked(LN {
+            tags: vec!["cultivation".to_owned()],
+            ..LN::new("Martial Peak".to_owned(), URL.to_owned(), 30)
+        });
+
+        let imported = vec![
+            LN {
+                status: Status::Ongoing,
+                tags: vec!["cultivation".to_owned(), "favourite".to_owned()],
+                ..LN::new("Martial Peak".to_owned(), URL.to_owned(), 10)
+            },
+            LN::new(
+                "Martial God".to_owned(),
+                "https://freewebnovel.com/martial-god.html".to_owned(),
+                1,
+            ),
+        ];
+
+        assert_eq!(merge(&mut data, imported), (1, 1));
+        assert_eq!(data.tracked()[0].last_chapter, 30);
+        assert_eq!(data.tracked()[0].status, Status::Ongoing);
+        assert_eq!(data.tracked()[0].tags, ["cultivation", "favourite"]);
+    }
+}